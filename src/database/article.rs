@@ -1,33 +1,83 @@
 use crate::database::Database;
 use crate::errors::Error;
-use crate::models::article::{Article, ArticleForCreate};
+use crate::models::{
+    article::{Article, ArticleForCreate, ArticleForUpdate},
+    tag::TagForCreate,
+};
+use crate::s3;
 use crate::server::context::Context;
-use crate::utils::OpChanges;
+use crate::utils::{html, image::Image, multipart, toc, OpChanges};
 
+use chrono::Duration;
+use serde::Deserialize;
 use surrealdb::{opt::PatchOp, sql::Thing};
 
 pub const ARTICLE_FOLDER: &str = "articles";
+pub const ARTICLE_IMAGE_FOLDER: &str = "images";
+pub const ARTICLE_COVER_FOLDER: &str = "cover";
+const EXCERPT_LENGTH: usize = 280;
 pub const ARTICLE_TBL_NAME: &str = "article";
 
+// Which public articles to list, used by feeds
+pub enum ArticleSource {
+    Site,
+    User(Thing),
+    Tag(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct ArticleSlugs {
+    slug: String,
+    slug_history: Option<Vec<String>>,
+}
+
 impl Database {
     pub async fn create_article_table(&self) -> Result<(), Error> {
         let sql = r#"
             DEFINE TABLE article SCHEMAFULL;
             DEFINE FIELD user_id              ON TABLE article TYPE record(user)    ASSERT $value != NONE;
             DEFINE FIELD title                ON TABLE article TYPE string          ASSERT $value != NONE;
+            DEFINE FIELD slug                 ON TABLE article TYPE string          ASSERT $value != NONE;
+            DEFINE FIELD slug_history         ON TABLE article TYPE array;
+            DEFINE FIELD slug_history.*       ON TABLE article TYPE string;
             DEFINE FIELD public               ON TABLE article TYPE bool            ASSERT $value != NONE;
+            DEFINE FIELD unlisted             ON TABLE article TYPE bool;
+            DEFINE FIELD hidden               ON TABLE article TYPE bool;
             DEFINE FIELD tags                 ON TABLE article TYPE array;
             DEFINE FIELD tags.*               ON TABLE article TYPE string;
             DEFINE FIELD article_uri          ON TABLE article TYPE string;
             DEFINE FIELD comments             ON TABLE article TYPE array;
             DEFINE FIELD comments.*           ON TABLE article TYPE record(comment) ASSERT $value != NONE;
-            DEFINE FIELD liked_by             ON TABLE article TYPE array;
-            DEFINE FIELD liked_by.*           ON TABLE article TYPE record(user)    ASSERT $value != NONE;
+            DEFINE FIELD like_count           ON TABLE article TYPE int             VALUE $value OR 0;
+            DEFINE FIELD reactions            ON TABLE article TYPE array;
+            DEFINE FIELD reactions.*          ON TABLE article TYPE object;
+            DEFINE FIELD reactions.*.reaction ON TABLE article TYPE string;
+            DEFINE FIELD reactions.*.count    ON TABLE article TYPE int;
             DEFINE FIELD images_uri_list      ON TABLE article TYPE array;
             DEFINE FIELD images_uri_list.*    ON TABLE article TYPE string;
+            DEFINE FIELD cover_uri            ON TABLE article TYPE string;
+            DEFINE FIELD excerpt              ON TABLE article TYPE string;
+            DEFINE FIELD excerpt_is_custom    ON TABLE article TYPE bool;
+            DEFINE FIELD description          ON TABLE article TYPE string;
+            DEFINE FIELD word_count           ON TABLE article TYPE int;
+            DEFINE FIELD reading_time         ON TABLE article TYPE int;
+            DEFINE FIELD moderation           ON TABLE article TYPE string;
+            DEFINE FIELD comments_locked      ON TABLE article TYPE bool;
+            DEFINE FIELD comments_closed_at   ON TABLE article TYPE datetime;
+            DEFINE FIELD toc                  ON TABLE article TYPE array;
+            DEFINE FIELD toc.*                ON TABLE article TYPE object;
+            DEFINE FIELD toc.*.level          ON TABLE article TYPE int             ASSERT $value != NONE;
+            DEFINE FIELD toc.*.title          ON TABLE article TYPE string          ASSERT $value != NONE;
+            DEFINE FIELD toc.*.anchor         ON TABLE article TYPE string          ASSERT $value != NONE;
             DEFINE FIELD created_at           ON TABLE article TYPE datetime        ASSERT $value != NONE;
             DEFINE FIELD updated_at           ON TABLE article TYPE datetime;
-            DEFINE INDEX liked_by_index       ON TABLE article COLUMNS liked_by.*   UNIQUE;
+            REMOVE INDEX liked_by_index       ON TABLE article;
+            REMOVE FIELD liked_by.*           ON TABLE article;
+            REMOVE FIELD liked_by             ON TABLE article;
+            UPDATE article SET liked_by = NONE WHERE liked_by != NONE;
+            UPDATE article SET like_count = count((SELECT id FROM likes WHERE out = $parent.id)) WHERE like_count = NONE;
+            UPDATE article SET slug = meta::id(id) WHERE slug = NONE;
+            DEFINE INDEX slug_index           ON TABLE article COLUMNS user_id, slug UNIQUE;
         "#;
 
         self.client.query(sql).await.map_err(|err| {
@@ -41,6 +91,11 @@ impl Database {
     pub async fn create_article(&self, info: &mut ArticleForCreate) -> Result<Thing, Error> {
         info.created_at = chrono::offset::Utc::now();
         info.article_uri = String::from("");
+        let tags = TagForCreate::from_names(&info.tags);
+        info.tags = tags.iter().map(|tag| tag.slug.clone()).collect();
+        info.slug = self
+            .generate_article_slug(&info.user_id, &info.title, None)
+            .await?;
 
         let article: Article = self
             .client
@@ -48,6 +103,9 @@ impl Database {
             .content(info)
             .await
             .map_err(|err| Error::DBCouldNotCreateRecord(err.to_string()))?;
+        self.sync_tags(&tags).await?;
+        self.index_article(&article, None).await?;
+        self.related.clear();
 
         Ok(article.id)
     }
@@ -87,12 +145,180 @@ impl Database {
         Ok(article)
     }
 
+    pub async fn list_public_articles(
+        &self,
+        source: &ArticleSource,
+        limit: Option<usize>,
+    ) -> Result<Vec<Article>, Error> {
+        let filter = match source {
+            ArticleSource::Site => "",
+            ArticleSource::User(_) => "AND user_id = $user_id",
+            ArticleSource::Tag(_) => "AND tags CONTAINS $tag",
+        };
+        let limit = match limit {
+            Some(limit) => format!("LIMIT {}", limit),
+            None => String::new(),
+        };
+        let sql = format!(
+            "SELECT * FROM {} WHERE public = true AND unlisted != true AND hidden != true {} ORDER BY created_at DESC {}",
+            ARTICLE_TBL_NAME, filter, limit
+        );
+
+        let mut query = self.client.query(sql);
+        match source {
+            ArticleSource::Site => {}
+            ArticleSource::User(user_id) => query = query.bind(("user_id", user_id)),
+            ArticleSource::Tag(tag) => query = query.bind(("tag", tag)),
+        }
+        let articles: Vec<Article> = query
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(articles)
+    }
+
+    // Drafts and hidden articles only exist for their author and admins
+    pub fn check_article_visible(
+        &self,
+        article: &Article,
+        context: &Option<Context>,
+    ) -> Result<(), Error> {
+        if article.public && !article.hidden {
+            return Ok(());
+        }
+
+        match context {
+            Some(context)
+                if context
+                    .check_permissions(Some(article.user_id.clone()), false)
+                    .is_ok() =>
+            {
+                Ok(())
+            }
+            _ => Err(Error::DBRecordDidNotExist(article.id.to_string())),
+        }
+    }
+
+    // Locked articles refuse comments outright, otherwise they close at the date set on the
+    // article, which wins over the auto close policy of its author
+    pub async fn check_comments_open(&self, article: &Article) -> Result<(), Error> {
+        if article.comments_locked {
+            return Err(Error::ServerCommentsLocked(
+                article.id.to_string(),
+                String::from("Comments were locked by the author"),
+            ));
+        }
+
+        let closed_at = match article.comments_closed_at {
+            Some(closed_at) => Some(closed_at),
+            None => self
+                .get_user_with_id(&article.user_id)
+                .await?
+                .comments_auto_close_days
+                .filter(|days| *days > 0)
+                .map(|days| article.created_at + Duration::days(days.into())),
+        };
+        match closed_at {
+            Some(closed_at) if closed_at <= chrono::offset::Utc::now() => {
+                Err(Error::ServerCommentsLocked(
+                    article.id.to_string(),
+                    format!("Comments were closed on {}", closed_at.to_rfc3339()),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Looks the article up by its current slug first, then by the slugs it used to have
+    pub async fn get_article_with_slug(
+        &self,
+        user_id: &Thing,
+        slug: &str,
+    ) -> Result<Article, Error> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE user_id = $user_id AND (slug = $slug OR slug_history CONTAINS $slug)",
+            ARTICLE_TBL_NAME
+        );
+        let articles: Vec<Article> = self
+            .client
+            .query(sql)
+            .bind(("user_id", user_id))
+            .bind(("slug", slug))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(slug.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        articles
+            .into_iter()
+            .min_by_key(|article| article.slug != slug)
+            .ok_or(Error::DBRecordDidNotExist(slug.to_string()))
+    }
+
+    async fn generate_article_slug(
+        &self,
+        user_id: &Thing,
+        title: &str,
+        article_id: Option<&Thing>,
+    ) -> Result<String, Error> {
+        let mut base = slug::slugify(title);
+        if base.is_empty() {
+            base = ARTICLE_TBL_NAME.to_string();
+        }
+
+        let sql = format!(
+            "SELECT slug, slug_history FROM {} WHERE user_id = $user_id AND id != $article_id",
+            ARTICLE_TBL_NAME
+        );
+        let taken: Vec<ArticleSlugs> = self
+            .client
+            .query(sql)
+            .bind(("user_id", user_id))
+            .bind(("article_id", article_id))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(user_id.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let is_taken = |slug: &str| {
+            taken.iter().any(|article| {
+                article.slug == slug
+                    || article
+                        .slug_history
+                        .as_ref()
+                        .is_some_and(|history| history.iter().any(|element| element == slug))
+            })
+        };
+
+        let mut slug = base.clone();
+        let mut suffix = 2;
+        while is_taken(&slug) {
+            slug = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        Ok(slug)
+    }
+
     pub async fn delete_article_with_id(&self, id: &Thing) -> Result<Article, Error> {
         let article: Article = self
             .client
             .delete((id.tb.clone(), id.id.clone()))
             .await
             .map_err(|err| Error::DBCouldNotDeleteRecord(id.to_string(), err.to_string()))?;
+        if let Some(tags) = &article.tags {
+            self.refresh_tag_counts(tags).await?;
+        }
+        self.remove_article_from_all_series(id).await?;
+        self.remove_article_from_search(id).await?;
+        self.related.clear();
+        if let Some(images) = &article.images_uri_list {
+            s3::delete_objects(images).await?;
+        }
+        if let Some(cover) = &article.cover_uri {
+            s3::delete_objects(std::slice::from_ref(cover)).await?;
+        }
 
         log::debug!(
             "Successfully delete article with id: {}. article: {:?}",
@@ -120,4 +346,340 @@ impl Database {
         );
         Ok(())
     }
+
+    pub async fn update_article(
+        &self,
+        article: &Article,
+        info: &ArticleForUpdate,
+    ) -> Result<(), Error> {
+        let mut update = self
+            .client
+            .update((article.id.tb.clone(), article.id.id.clone()))
+            .patch(PatchOp::replace("/updated_at", chrono::offset::Utc::now()));
+
+        if let Some(title) = &info.title {
+            update = update.patch(PatchOp::replace("/title", title));
+
+            let slug = self
+                .generate_article_slug(&article.user_id, title, Some(&article.id))
+                .await?;
+            if article.slug != slug {
+                let mut history = article.slug_history.clone().unwrap_or_default();
+                history.retain(|element| element != &slug);
+                history.push(article.slug.clone());
+                update = update
+                    .patch(PatchOp::replace("/slug", slug))
+                    .patch(PatchOp::replace("/slug_history", history));
+            }
+        }
+
+        if let Some(public) = info.public {
+            update = update.patch(PatchOp::replace("/public", public));
+        }
+        if let Some(unlisted) = info.unlisted {
+            update = update.patch(PatchOp::replace("/unlisted", unlisted));
+        }
+        if let Some(moderation) = info.moderation {
+            update = update.patch(PatchOp::replace("/moderation", moderation));
+        }
+        if let Some(comments_locked) = info.comments_locked {
+            update = update.patch(PatchOp::replace("/comments_locked", comments_locked));
+        }
+        if let Some(comments_closed_at) = info.comments_closed_at {
+            update = update.patch(PatchOp::replace("/comments_closed_at", comments_closed_at));
+        }
+
+        let mut unused_images: Vec<String> = Default::default();
+        if let Some(content) = &info.content {
+            let (used, unused): (Vec<String>, Vec<String>) = article
+                .images_uri_list
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|uri| content.contains(uri.as_str()));
+            if !unused.is_empty() {
+                update = update.patch(PatchOp::replace("/images_uri_list", used));
+                unused_images = unused;
+            }
+
+            let word_count = toc::word_count(content);
+            update = update
+                .patch(PatchOp::replace("/word_count", word_count))
+                .patch(PatchOp::replace(
+                    "/reading_time",
+                    toc::reading_time(word_count),
+                ))
+                .patch(PatchOp::replace("/toc", toc::table_of_contents(content)));
+        }
+
+        match &info.excerpt {
+            Some(excerpt) if !excerpt.is_empty() => {
+                update = update
+                    .patch(PatchOp::replace("/excerpt", excerpt))
+                    .patch(PatchOp::replace("/excerpt_is_custom", true));
+            }
+            Some(_) => {
+                let content = match &info.content {
+                    Some(content) => content.clone(),
+                    None => s3::get_object(&article.article_uri).await?,
+                };
+                update = match derive_excerpt(&content) {
+                    Some(excerpt) => update.patch(PatchOp::replace("/excerpt", excerpt)),
+                    None => update.patch(PatchOp::remove("/excerpt")),
+                }
+                .patch(PatchOp::replace("/excerpt_is_custom", false));
+            }
+            None => {
+                if let (Some(content), false) = (&info.content, article.excerpt_is_custom) {
+                    if let Some(excerpt) = derive_excerpt(content) {
+                        update = update.patch(PatchOp::replace("/excerpt", excerpt));
+                    }
+                }
+            }
+        }
+
+        if let Some(description) = &info.description {
+            update = if description.is_empty() {
+                update.patch(PatchOp::remove("/description"))
+            } else {
+                update.patch(PatchOp::replace("/description", description))
+            };
+        }
+
+        let mut tags: Vec<TagForCreate> = Default::default();
+        if let Some(names) = &info.tags {
+            tags = TagForCreate::from_names(names);
+            let slugs: Vec<String> = tags.iter().map(|tag| tag.slug.clone()).collect();
+            update = update.patch(PatchOp::replace("/tags", slugs));
+        }
+
+        let changes: Vec<OpChanges> = update.await.map_err(|err| {
+            Error::DBCouldNotUpdateRecord(article.id.to_string(), err.to_string())
+        })?;
+
+        s3::delete_objects(&unused_images).await?;
+        if let Some(cover) = &info.cover {
+            self.update_article_cover(&article.user_id, &article.id, cover)
+                .await?;
+            if let Some(old_cover) = &article.cover_uri {
+                s3::delete_objects(std::slice::from_ref(old_cover)).await?;
+            }
+        }
+        if info.tags.is_some() {
+            self.sync_tags(&tags).await?;
+            if let Some(old_tags) = &article.tags {
+                self.refresh_tag_counts(old_tags).await?;
+            }
+        } else if info.public.is_some() || info.unlisted.is_some() {
+            if let Some(old_tags) = &article.tags {
+                self.refresh_tag_counts(old_tags).await?;
+            }
+        }
+        if info.title.is_some() || info.content.is_some() || info.public.is_some() {
+            let updated = self.get_article_with_id(&article.id).await?;
+            let content = match &info.content {
+                Some(content) => Some(self.process_article_mentions(&updated, content).await?),
+                // Mentions of drafts are not saved, publishing notifies all of them
+                None if updated.public && !article.public => {
+                    let content = s3::get_object(&article.article_uri).await?;
+                    self.process_article_mentions(&updated, &content).await?;
+                    None
+                }
+                None => None,
+            };
+            if info.title.is_some() || info.content.is_some() {
+                self.index_article(&updated, content.as_deref()).await?;
+            }
+        }
+        if info.title.is_some()
+            || info.content.is_some()
+            || info.tags.is_some()
+            || info.public.is_some()
+            || info.unlisted.is_some()
+        {
+            self.related.clear();
+        }
+
+        log::debug!(
+            "Successfully updated article with id: `{}`, changes: {:?}",
+            &article.id,
+            changes
+        );
+        Ok(())
+    }
+
+    // Images are stored next to the article so they can be referenced from its body
+    pub async fn add_article_images(
+        &self,
+        article: &Article,
+        images: &[Image],
+    ) -> Result<Vec<String>, Error> {
+        let folder = format!(
+            "{}/{}/{}/{}",
+            article.user_id, ARTICLE_FOLDER, article.id, ARTICLE_IMAGE_FOLDER
+        );
+        let mut uris = Vec::new();
+        for image in images {
+            uris.push(multipart::upload_user_image_to_s3(&folder, image).await?);
+        }
+
+        let changes: Vec<OpChanges> = self
+            .client
+            .update((article.id.tb.clone(), article.id.id.clone()))
+            .patch(PatchOp::add("/images_uri_list", uris.clone()))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotUpdateRecord(article.id.to_string(), err.to_string())
+            })?;
+        log::debug!(
+            "Successfully add images to article with id: `{}`, changes: {:?}",
+            &article.id,
+            changes
+        );
+
+        Ok(uris)
+    }
+
+    pub async fn update_article_cover(
+        &self,
+        user_id: &Thing,
+        article_id: &Thing,
+        cover: &Image,
+    ) -> Result<String, Error> {
+        let uri = multipart::upload_user_image_to_s3(
+            &format!(
+                "{}/{}/{}/{}",
+                user_id, ARTICLE_FOLDER, article_id, ARTICLE_COVER_FOLDER
+            ),
+            cover,
+        )
+        .await?;
+
+        let changes: Vec<OpChanges> = self
+            .client
+            .update((article_id.tb.clone(), article_id.id.clone()))
+            .patch(PatchOp::replace("/cover_uri", &uri))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotUpdateRecord(article_id.to_string(), err.to_string())
+            })?;
+        log::debug!(
+            "Successfully updated cover of article with id: `{}`, changes: {:?}",
+            &article_id,
+            changes
+        );
+
+        Ok(uri)
+    }
+}
+
+fn derive_excerpt(content: &str) -> Option<String> {
+    html::first_paragraph(content).map(|paragraph| html::truncate_words(&paragraph, EXCERPT_LENGTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::Role;
+
+    // `article:one` by `user:author`, who closes comments after `days`, with `fields` set on top
+    async fn create_article(database: &Database, days: u32, fields: &str) -> Article {
+        let sql = format!(
+            "CREATE user:author SET first_name = 'Ada', last_name = 'Lovelace', username = 'ada', \
+             email = 'ada@example.com', is_admin = false, deleted = false, \
+             comments_auto_close_days = {}, created_at = time::now();
+             CREATE article:one SET user_id = user:author, title = 'One', slug = 'one', \
+             public = true, article_uri = 'articles/one.md', {};",
+            days, fields
+        );
+        database.client.query(sql).await.unwrap();
+
+        database
+            .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, "one")))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn check_article_visible_keeps_hidden_articles_to_the_author_and_admins() {
+        let database = Database::start_in_memory().await;
+        let article = create_article(&database, 0, "hidden = true, created_at = time::now()").await;
+        let context = |user_id: &str, user_role: Role| {
+            Some(Context {
+                user_id: Thing::from(("user", user_id)),
+                user_role,
+            })
+        };
+
+        assert!(matches!(
+            database.check_article_visible(&article, &None),
+            Err(Error::DBRecordDidNotExist(_))
+        ));
+        assert!(matches!(
+            database.check_article_visible(&article, &context("reader", Role::User)),
+            Err(Error::DBRecordDidNotExist(_))
+        ));
+        assert!(database
+            .check_article_visible(&article, &context("author", Role::User))
+            .is_ok());
+        assert!(database
+            .check_article_visible(&article, &context("admin", Role::Admin))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn check_comments_open_rejects_locked_articles() {
+        let database = Database::start_in_memory().await;
+        let article = create_article(
+            &database,
+            0,
+            "comments_locked = true, created_at = time::now()",
+        )
+        .await;
+
+        assert!(matches!(
+            database.check_comments_open(&article).await,
+            Err(Error::ServerCommentsLocked(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn check_comments_open_rejects_closed_articles() {
+        let database = Database::start_in_memory().await;
+        let article = create_article(
+            &database,
+            0,
+            "comments_closed_at = time::now() - 1h, created_at = time::now()",
+        )
+        .await;
+
+        assert!(matches!(
+            database.check_comments_open(&article).await,
+            Err(Error::ServerCommentsLocked(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn check_comments_open_closes_after_the_author_auto_close_days() {
+        let database = Database::start_in_memory().await;
+        let article = create_article(&database, 7, "created_at = time::now() - 8d").await;
+        assert!(matches!(
+            database.check_comments_open(&article).await,
+            Err(Error::ServerCommentsLocked(..))
+        ));
+
+        let database = Database::start_in_memory().await;
+        let article = create_article(&database, 7, "created_at = time::now() - 6d").await;
+        assert!(database.check_comments_open(&article).await.is_ok());
+
+        // The closing date of the article wins over the policy of the author
+        let database = Database::start_in_memory().await;
+        let article = create_article(
+            &database,
+            7,
+            "comments_closed_at = time::now() + 1d, created_at = time::now() - 8d",
+        )
+        .await;
+        assert!(database.check_comments_open(&article).await.is_ok());
+    }
 }
@@ -1,13 +1,90 @@
 use crate::database::{article::ARTICLE_FOLDER, Database};
 use crate::errors::Error;
-use crate::models::comment::{Comment, CommentForCreate};
-use crate::server::context::Context;
+use crate::models::comment::{
+    Comment, CommentEntry, CommentForCreate, CommentNode, CommentPage, CommentQuery,
+    CommentRevision, CommentRevisionForCreate, CommentSort, CommentStatus, ModerationMode,
+    ModerationQuery,
+};
+use crate::models::{article::Article, spam::SpamLabel, user::Role};
+use crate::s3;
+use crate::server::{config::SiteConfig, context::Context};
 use crate::{utils, utils::OpChanges};
 
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use surrealdb::{opt::PatchOp, sql::Thing};
 
 pub const COMMENT_TBL_NAME: &str = "comment";
+pub const COMMENT_REVISION_TBL_NAME: &str = "comment_revision";
 pub const COMMENT_FOLDER: &str = "comments";
+const COMMENT_TREE_DEPTH: usize = 3;
+const COMMENT_TREE_MAX_DEPTH: usize = 10;
+const COMMENT_PAGE_LENGTH: usize = 20;
+const COMMENT_MAX_PAGE_LENGTH: usize = 100;
+const MODERATION_PAGE_LENGTH: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct CommentCount {
+    total: usize,
+}
+
+// Encoded as `<like count>_<created_at in nanoseconds>_<id>`, the like count is only there
+// when sorting by likes
+struct CommentCursor {
+    like_count: usize,
+    created_at: DateTime<Utc>,
+    id: String,
+}
+
+impl CommentCursor {
+    fn from_comment(comment: &Comment, sort: CommentSort) -> Self {
+        CommentCursor {
+            like_count: match sort {
+                CommentSort::MostLiked => comment.like_count,
+                _ => 0,
+            },
+            created_at: comment.created_at,
+            id: comment.id.id.to_raw(),
+        }
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "{}_{}_{}",
+            self.like_count,
+            self.created_at.timestamp_nanos(),
+            self.id
+        )
+    }
+
+    fn decode(cursor: &str, sort: CommentSort) -> Result<Self, Error> {
+        let invalid = || Error::ServerBadRequest(format!("Invalid cursor: `{}`", cursor));
+        let mut parts = cursor.splitn(3, '_');
+        let like_count: usize = parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(invalid)?;
+        let created_at: i64 = parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(invalid)?;
+        let id = parts
+            .next()
+            .filter(|id| !id.is_empty())
+            .ok_or_else(invalid)?;
+
+        Ok(CommentCursor {
+            like_count: if sort == CommentSort::MostLiked {
+                like_count
+            } else {
+                0
+            },
+            created_at: Utc.timestamp_nanos(created_at),
+            id: id.to_string(),
+        })
+    }
+}
 
 impl Database {
     pub async fn create_comment_table(&self) -> Result<(), Error> {
@@ -18,14 +95,29 @@ impl Database {
             DEFINE FIELD reply                   ON TABLE comment TYPE array;
             DEFINE FIELD reply.*                 ON TABLE comment TYPE record(comment)   ASSERT $value != NONE;
             DEFINE FIELD content                 ON TABLE comment TYPE string;
+            DEFINE FIELD content_html            ON TABLE comment TYPE string;
             DEFINE FIELD deleted                 ON TABLE comment TYPE bool              ASSERT $value != NONE;
+            DEFINE FIELD status                  ON TABLE comment TYPE string            ASSERT $value INSIDE ["pending", "approved", "rejected", "spam"];
+            DEFINE FIELD hidden                  ON TABLE comment TYPE bool;
+            DEFINE FIELD spam_score              ON TABLE comment TYPE float;
+            DEFINE FIELD spam_label              ON TABLE comment TYPE string;
             DEFINE FIELD media_uri               ON TABLE comment TYPE string;
-            DEFINE FIELD liked_by                ON TABLE comment TYPE array;
-            DEFINE FIELD liked_by.*              ON TABLE comment TYPE record(user)      ASSERT $value != NONE;
+            DEFINE FIELD like_count              ON TABLE comment TYPE int               VALUE $value OR 0;
+            DEFINE FIELD reactions               ON TABLE comment TYPE array;
+            DEFINE FIELD reactions.*             ON TABLE comment TYPE object;
+            DEFINE FIELD reactions.*.reaction    ON TABLE comment TYPE string;
+            DEFINE FIELD reactions.*.count       ON TABLE comment TYPE int;
+            DEFINE FIELD edited                  ON TABLE comment TYPE bool;
             DEFINE FIELD created_at              ON TABLE comment TYPE datetime          ASSERT $value != NONE;
             DEFINE FIELD updated_at              ON TABLE comment TYPE datetime;
             DEFINE FIELD deleted_at              ON TABLE comment TYPE datetime;
-            DEFINE INDEX liked_by_index          ON TABLE comment COLUMNS liked_by       UNIQUE;
+            REMOVE INDEX liked_by_index          ON TABLE comment;
+            REMOVE FIELD liked_by.*              ON TABLE comment;
+            REMOVE FIELD liked_by                ON TABLE comment;
+            UPDATE comment SET liked_by = NONE WHERE liked_by != NONE;
+            UPDATE comment SET like_count = count((SELECT id FROM likes WHERE out = $parent.id)) WHERE like_count = NONE;
+            UPDATE comment SET status = "approved" WHERE status = NONE;
+            UPDATE comment SET edited = true WHERE edited = NONE AND updated_at != NONE;
         "#;
 
         self.client.query(sql).await.map_err(|err| {
@@ -36,8 +128,31 @@ impl Database {
         Ok(())
     }
 
+    pub async fn create_comment_revision_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE TABLE comment_revision SCHEMAFULL;
+            DEFINE FIELD comment_id              ON TABLE comment_revision TYPE record(comment) ASSERT $value != NONE;
+            DEFINE FIELD content                 ON TABLE comment_revision TYPE string;
+            DEFINE FIELD media_uri               ON TABLE comment_revision TYPE string;
+            DEFINE FIELD created_at              ON TABLE comment_revision TYPE datetime        ASSERT $value != NONE;
+            DEFINE FIELD replaced_at             ON TABLE comment_revision TYPE datetime        ASSERT $value != NONE;
+            DEFINE INDEX comment_revision_index  ON TABLE comment_revision COLUMNS comment_id;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(COMMENT_REVISION_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", COMMENT_REVISION_TBL_NAME);
+
+        Ok(())
+    }
+
     pub async fn create_comment(&self, info: &mut CommentForCreate) -> Result<Thing, Error> {
         info.created_at = chrono::offset::Utc::now();
+        info.content_html = Some(
+            self.render_comment_content(&info.user_id, info.content.as_deref())
+                .await?,
+        );
 
         let comment: Comment = self
             .client
@@ -45,6 +160,8 @@ impl Database {
             .content(info)
             .await
             .map_err(|err| Error::DBCouldNotCreateRecord(err.to_string()))?;
+        self.index_comment(&comment).await?;
+        self.process_comment_mentions(&comment).await?;
 
         Ok(comment.id)
     }
@@ -56,6 +173,10 @@ impl Database {
         info: &mut CommentForCreate,
     ) -> Result<Thing, Error> {
         info.created_at = chrono::offset::Utc::now();
+        info.content_html = Some(
+            self.render_comment_content(&info.user_id, info.content.as_deref())
+                .await?,
+        );
 
         let comment: Comment = self
             .client
@@ -63,6 +184,8 @@ impl Database {
             .content(&info)
             .await
             .map_err(|err| Error::DBCouldNotCreateRecord(err.to_string()))?;
+        self.index_comment(&comment).await?;
+        self.process_comment_mentions(&comment).await?;
         let id = comment.id.clone();
 
         if let Some(media) = &info.image {
@@ -120,64 +243,102 @@ impl Database {
         Ok(comment)
     }
 
-    pub async fn delete_comment(&self, comment: &Thing) -> Result<(), Error> {
-        let changes: Vec<OpChanges> = self
-            .client
-            .update((comment.tb.clone(), comment.id.clone()))
-            .patch(PatchOp::replace("/deleted", true))
-            .patch(PatchOp::replace("/deleted_at", chrono::offset::Utc::now()))
+    // The record stays so its replies keep their parent, but the media is not needed anymore.
+    // Earlier versions are kept for moderators, along with the media they still point to
+    pub async fn delete_comment(&self, comment: &Comment) -> Result<(), Error> {
+        self.client
+            .query("UPDATE $comment SET deleted = true, deleted_at = time::now(), media_uri = NONE")
+            .bind(("comment", &comment.id))
             .await
-            .map_err(|err| Error::DBCouldNotDeleteRecord(comment.to_string(), err.to_string()))?;
+            .map_err(|err| {
+                Error::DBCouldNotDeleteRecord(comment.id.to_string(), err.to_string())
+            })?;
         log::debug!(
-            "Successfully marked comment with id: `{}` as deleted. Changes: {:?}",
-            comment,
-            changes
+            "Successfully marked comment with id: `{}` as deleted",
+            comment.id
         );
 
-        Ok(())
-    }
-
-    pub async fn get_reply_for_comment(&self, comment: &Thing) -> Result<Vec<Comment>, Error> {
-        let get_reply_id_sql = format!("SELECT reply FROM comment WHERE id = {}", comment);
-        let _ids: Vec<Thing> = self
-            .client
-            .query(get_reply_id_sql)
-            .await
-            .map_err(|err| Error::DBCouldNotSelectRecord(comment.to_string(), err.to_string()))?
-            .take(0)
-            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
-
-        let sql = format!(
-            "SELECT * FROM comment WHERE (SELECT reply FROM comment WHERE id = {})",
-            comment
-        );
-        let reply: Vec<Comment> = self
-            .client
-            .query(sql)
-            .await
-            .map_err(|err| Error::DBCouldNotSelectRecord(comment.to_string(), err.to_string()))?
-            .take(0)
-            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let Some(media_uri) = comment.media_uri.clone() else {
+            return Ok(());
+        };
+        let revisions = self.list_comment_revisions(&comment.id).await?;
+        if revisions
+            .iter()
+            .all(|revision| revision.media_uri.as_ref() != Some(&media_uri))
+        {
+            tokio::spawn(async move {
+                if let Err(err) = s3::delete_objects(std::slice::from_ref(&media_uri)).await {
+                    log::error!("Could not delete comment media `{}`: {:?}", media_uri, err);
+                }
+            });
+        }
 
-        Ok(reply)
+        Ok(())
     }
 
+    // New content is checked again like a new comment, which can hold a published comment but
+    // never publishes one that was held or refused. Returns the status of the comment
     pub async fn update_comment(
         &self,
+        context: &Context,
+        article: &Article,
         old_comment: &Comment,
         new_comment: &mut CommentForCreate,
-    ) -> Result<(), Error> {
+    ) -> Result<CommentStatus, Error> {
         let current_info = filter_empty_field(old_comment, new_comment).await?;
+        let content_changed = current_info.content != old_comment.content;
+        if !content_changed && current_info.media_uri == old_comment.media_uri {
+            return Ok(old_comment.status);
+        }
+
+        let (status, spam_score) = if content_changed {
+            let (status, spam_score) = self
+                .get_moderation_status(context, article, current_info.content.as_deref())
+                .await?;
+            if old_comment.status == CommentStatus::Approved {
+                (status, spam_score)
+            } else {
+                (old_comment.status, spam_score)
+            }
+        } else {
+            (old_comment.status, old_comment.spam_score)
+        };
+        // What the filter learnt from the label belongs to the content being replaced
+        if content_changed && old_comment.spam_label.is_some() {
+            self.train_spam_filter(old_comment, None).await?;
+        }
+
+        // The version being replaced is kept, along with its media
+        let revision = CommentRevisionForCreate {
+            comment_id: old_comment.id.clone(),
+            content: old_comment.content.clone(),
+            media_uri: old_comment.media_uri.clone(),
+            created_at: old_comment.updated_at.unwrap_or(old_comment.created_at),
+            replaced_at: chrono::offset::Utc::now(),
+        };
+        let _: CommentRevision = self
+            .client
+            .create(COMMENT_REVISION_TBL_NAME)
+            .content(revision)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateRecord(err.to_string()))?;
+
+        let content_html = self
+            .render_comment_content(&old_comment.user_id, current_info.content.as_deref())
+            .await?;
         let changes: Vec<OpChanges> = self
             .client
             .update((old_comment.id.tb.clone(), old_comment.id.id.clone()))
             .patch(PatchOp::replace("/content", current_info.content.clone()))
+            .patch(PatchOp::replace("/content_html", content_html))
+            .patch(PatchOp::replace("/status", status))
+            .patch(PatchOp::replace("/spam_score", spam_score))
             .patch(PatchOp::replace(
                 "/media_uri",
                 current_info.media_uri.clone(),
             ))
             .patch(PatchOp::replace("/updated_at", current_info.updated_at))
-            .patch(PatchOp::replace("/deleted", current_info.deleted))
+            .patch(PatchOp::replace("/edited", true))
             .await
             .map_err(|err| {
                 Error::DBCouldNotUpdateRecord(old_comment.id.to_string(), err.to_string())
@@ -187,24 +348,426 @@ impl Database {
             old_comment.id,
             changes
         );
+        let comment = self.get_comment(&old_comment.id).await?;
+        self.index_comment(&comment).await?;
+        self.process_comment_mentions(&comment).await?;
+
+        Ok(status)
+    }
+
+    // Oldest first, the current version is the comment itself
+    pub async fn list_comment_revisions(
+        &self,
+        comment: &Thing,
+    ) -> Result<Vec<CommentRevision>, Error> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE comment_id = $comment_id ORDER BY replaced_at ASC",
+            COMMENT_REVISION_TBL_NAME
+        );
+        let revisions: Vec<CommentRevision> = self
+            .client
+            .query(sql)
+            .bind(("comment_id", comment))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(comment.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(revisions)
+    }
+
+    pub async fn get_comment_for_article(
+        &self,
+        article: &Thing,
+        query: &CommentQuery,
+    ) -> Result<CommentPage, Error> {
+        let sql = format!(
+            "SELECT VALUE reply FROM {} WHERE article_id = $article_id AND reply != NONE",
+            COMMENT_TBL_NAME
+        );
+        let replies: Vec<Vec<Thing>> = self
+            .client
+            .query(sql)
+            .bind(("article_id", article))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(article.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let replies: Vec<Thing> = replies.into_iter().flatten().collect();
+
+        self.get_comment_page(article, "id NOTINSIDE $replies", &replies, query)
+            .await
+    }
+
+    pub async fn get_reply_for_comment(
+        &self,
+        comment: &Comment,
+        query: &CommentQuery,
+    ) -> Result<CommentPage, Error> {
+        let replies = comment.reply.clone().unwrap_or_default();
+
+        self.get_comment_page(&comment.article_id, "id INSIDE $replies", &replies, query)
+            .await
+    }
+
+    // Sets the status of a new comment on `article`, its author and admins skip the queue while
+    // likely spam is always held for a moderator
+    pub async fn moderate_comment(
+        &self,
+        context: &Context,
+        article: &Article,
+        comment: &mut CommentForCreate,
+    ) -> Result<(), Error> {
+        self.check_comments_open(article).await?;
+        let site = SiteConfig::parse_from_env_file()?;
+        if article.moderation.unwrap_or(site.comment_moderation) == ModerationMode::Closed {
+            return Err(Error::ServerCommentsLocked(
+                article.id.to_string(),
+                String::from("Comments are closed by the moderation settings"),
+            ));
+        }
+
+        let (status, spam_score) = self
+            .get_moderation_status(context, article, comment.content.as_deref())
+            .await?;
+        comment.status = status;
+        comment.spam_score = spam_score;
 
         Ok(())
     }
 
-    pub async fn get_comment_for_article(&self, article: &Thing) -> Result<Vec<Comment>, Error> {
+    // The spam score is only set when the content was checked
+    async fn get_moderation_status(
+        &self,
+        context: &Context,
+        article: &Article,
+        content: Option<&str>,
+    ) -> Result<(CommentStatus, Option<f64>), Error> {
+        if context.user_role == Role::Admin || context.user_id == article.user_id {
+            return Ok((CommentStatus::Approved, None));
+        }
+
+        let site = SiteConfig::parse_from_env_file()?;
+        let spam_score = self
+            .get_spam_score(content.unwrap_or_default(), &site.spam_blocklist)
+            .await?;
+        let mode = article.moderation.unwrap_or(site.comment_moderation);
+        let has_approved = spam_score < site.spam_threshold
+            && mode == ModerationMode::FirstTime
+            && self.has_approved_comment(&context.user_id).await?;
+        let status = moderation_status(spam_score, site.spam_threshold, mode, has_approved);
+        if spam_score >= site.spam_threshold {
+            log::info!(
+                "Holding comment of user: `{}` for moderation, spam score: {:.2}",
+                context.user_id,
+                spam_score
+            );
+        }
+
+        Ok((status, Some(spam_score)))
+    }
+
+    async fn has_approved_comment(&self, user_id: &Thing) -> Result<bool, Error> {
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE user_id = $user_id AND status = 'approved' GROUP ALL",
+            COMMENT_TBL_NAME
+        );
+        let approved: Option<CommentCount> = self
+            .client
+            .query(sql)
+            .bind(("user_id", user_id))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(user_id.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(approved.is_some_and(|approved| approved.total > 0))
+    }
+
+    // Oldest first so the queue is worked through in order, `article` limits it to one article
+    pub async fn get_moderation_queue(
+        &self,
+        query: &ModerationQuery,
+        article: Option<&Thing>,
+    ) -> Result<(usize, Vec<Comment>), Error> {
+        let status = query.status.unwrap_or(CommentStatus::Pending);
+        let filter = match article {
+            Some(_) => "status = $status AND article_id = $article_id",
+            None => "status = $status",
+        };
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE {} GROUP ALL; SELECT * FROM {} WHERE {} ORDER BY created_at ASC LIMIT {} START {};",
+            COMMENT_TBL_NAME,
+            filter,
+            COMMENT_TBL_NAME,
+            filter,
+            query
+                .limit
+                .unwrap_or(MODERATION_PAGE_LENGTH)
+                .clamp(1, COMMENT_MAX_PAGE_LENGTH),
+            query.offset.unwrap_or(0)
+        );
+
+        let mut response = self
+            .client
+            .query(sql)
+            .bind(("status", status))
+            .bind(("article_id", article))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(filter.to_string(), err.to_string()))?;
+        let total: Option<CommentCount> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let comments: Vec<Comment> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok((total.map(|total| total.total).unwrap_or(0), comments))
+    }
+
+    // Comments on articles that the user can not moderate are left untouched, returns the ids
+    // of the comments that were updated. Approved comments teach the spam filter what ham
+    // looks like, spam ones what spam looks like. Only admins can approve comments that were
+    // marked as spam or held by the spam filter
+    pub async fn set_comment_status(
+        &self,
+        context: &Context,
+        comments: &[Thing],
+        status: CommentStatus,
+    ) -> Result<Vec<Thing>, Error> {
+        if status == CommentStatus::Approved && context.user_role != Role::Admin {
+            let sql = format!(
+                "SELECT count() AS total FROM {} WHERE id INSIDE $comments AND (status = 'spam' OR spam_score >= $threshold) GROUP ALL",
+                COMMENT_TBL_NAME
+            );
+            let held: Option<CommentCount> = self
+                .client
+                .query(sql)
+                .bind(("comments", comments))
+                .bind((
+                    "threshold",
+                    SiteConfig::parse_from_env_file()?.spam_threshold,
+                ))
+                .await
+                .map_err(|err| Error::DBCouldNotSelectRecord(status.to_string(), err.to_string()))?
+                .take(0)
+                .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+            if held.is_some_and(|held| held.total > 0) {
+                context.check_permissions(None, true)?;
+            }
+        }
+
+        let sql = format!(
+            "UPDATE {} SET status = $status WHERE id INSIDE $comments AND ($is_admin = true OR article_id.user_id = $user_id) RETURN BEFORE",
+            COMMENT_TBL_NAME
+        );
+        let updated: Vec<Comment> = self
+            .client
+            .query(sql)
+            .bind(("status", status))
+            .bind(("comments", comments))
+            .bind(("is_admin", context.user_role == Role::Admin))
+            .bind(("user_id", &context.user_id))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(status.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        log::debug!(
+            "Successfully marked {} comment(s) as {}",
+            updated.len(),
+            status
+        );
+
+        let label = match status {
+            CommentStatus::Approved => Some(SpamLabel::Ham),
+            CommentStatus::Spam => Some(SpamLabel::Spam),
+            CommentStatus::Pending | CommentStatus::Rejected => None,
+        };
+        for comment in &updated {
+            self.train_spam_filter(comment, label).await?;
+            // Mentions in held comments are only notified once they are published
+            if status == CommentStatus::Approved && comment.status != CommentStatus::Approved {
+                self.process_comment_mentions(&self.get_comment(&comment.id).await?)
+                    .await?;
+            }
+        }
+
+        Ok(updated.into_iter().map(|comment| comment.id).collect())
+    }
+
+    // Keyset pagination, the cursor holds the sort key of the last comment of the previous page
+    async fn get_comment_page(
+        &self,
+        article: &Thing,
+        scope: &str,
+        replies: &[Thing],
+        query: &CommentQuery,
+    ) -> Result<CommentPage, Error> {
+        let limit = query
+            .limit
+            .unwrap_or(COMMENT_PAGE_LENGTH)
+            .clamp(1, COMMENT_MAX_PAGE_LENGTH);
+        let cursor = query
+            .cursor
+            .as_deref()
+            .map(|cursor| CommentCursor::decode(cursor, query.sort))
+            .transpose()?;
+
+        let (after, order) = match query.sort {
+            CommentSort::Oldest => (
+                "(created_at > $created_at OR (created_at = $created_at AND id > $id))",
+                "created_at ASC, id ASC",
+            ),
+            CommentSort::Newest => (
+                "(created_at < $created_at OR (created_at = $created_at AND id < $id))",
+                "created_at DESC, id DESC",
+            ),
+            CommentSort::MostLiked => (
+                "(like_count < $like_count OR (like_count = $like_count AND (created_at > $created_at OR (created_at = $created_at AND id > $id))))",
+                "like_count DESC, created_at ASC, id ASC",
+            ),
+        };
+        // Deleted comments without replies are left out, the others become tombstones
+        let scope = format!(
+            "{} AND status = 'approved' AND hidden != true AND (deleted = false OR count(reply) > 0)",
+            scope
+        );
+        let filter = match cursor {
+            Some(_) => format!("article_id = $article_id AND {} AND {}", scope, after),
+            None => format!("article_id = $article_id AND {}", scope),
+        };
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE article_id = $article_id AND {} GROUP ALL; SELECT * FROM {} WHERE {} ORDER BY {} LIMIT {};",
+            COMMENT_TBL_NAME,
+            scope,
+            COMMENT_TBL_NAME,
+            filter,
+            order,
+            limit + 1
+        );
+
+        let mut request = self
+            .client
+            .query(sql)
+            .bind(("article_id", article))
+            .bind(("replies", replies));
+        if let Some(cursor) = &cursor {
+            request = request
+                .bind(("created_at", cursor.created_at))
+                .bind(("id", Thing::from((COMMENT_TBL_NAME, cursor.id.as_str()))))
+                .bind(("like_count", cursor.like_count));
+        }
+        let mut response = request
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(article.to_string(), err.to_string()))?;
+        let total: Option<CommentCount> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let mut comments: Vec<Comment> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        let next_cursor = if comments.len() > limit {
+            comments.truncate(limit);
+            comments
+                .last()
+                .map(|comment| CommentCursor::from_comment(comment, query.sort).encode())
+        } else {
+            None
+        };
+
+        Ok(CommentPage {
+            comments: comments.into_iter().map(CommentEntry::from).collect(),
+            total: total.map(|total| total.total).unwrap_or(0),
+            next_cursor,
+        })
+    }
+
+    // The whole discussion is loaded in one query and assembled in process, starting either
+    // from the top level comments of the article or from the replies of `parent`
+    pub async fn get_comment_tree(
+        &self,
+        article_id: &Thing,
+        parent: Option<&Thing>,
+        query: &CommentQuery,
+    ) -> Result<(usize, Vec<CommentNode>), Error> {
         let sql = format!(
-            "SELECT * FROM comment WHERE (SELECT comments FROM article WHERE id = {})",
-            article
+            "SELECT * FROM {} WHERE article_id = $article_id",
+            COMMENT_TBL_NAME
         );
         let comments: Vec<Comment> = self
             .client
             .query(sql)
+            .bind(("article_id", article_id))
             .await
-            .map_err(|err| Error::DBCouldNotSelectRecord(article.to_string(), err.to_string()))?
+            .map_err(|err| Error::DBCouldNotSelectRecord(article_id.to_string(), err.to_string()))?
             .take(0)
             .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let mut comments: HashMap<String, Comment> = comments
+            .into_iter()
+            .map(|comment| (comment.id.to_string(), comment))
+            .collect();
+
+        // Roots are found before anything is filtered out, so the replies of a comment that is
+        // not shown go away with it instead of showing up as top level comments
+        let roots: Vec<String> = match parent {
+            Some(parent) => comments
+                .get(&parent.to_string())
+                .ok_or(Error::DBRecordDidNotExist(parent.to_string()))?
+                .reply
+                .iter()
+                .flatten()
+                .map(|id| id.to_string())
+                .collect(),
+            None => {
+                let replies: HashSet<String> = comments
+                    .values()
+                    .flat_map(|comment| comment.reply.iter().flatten())
+                    .map(|id| id.to_string())
+                    .collect();
+                comments
+                    .keys()
+                    .filter(|id| !replies.contains(*id))
+                    .cloned()
+                    .collect()
+            }
+        };
+
+        // Deleted comments without replies are left out, the others become tombstones
+        comments.retain(|_, comment| {
+            comment.status == CommentStatus::Approved
+                && !comment.hidden
+                && (!comment.deleted || comment.has_replies())
+        });
+        if let Some(parent) = parent {
+            if !comments.contains_key(&parent.to_string()) {
+                return Err(Error::DBRecordDidNotExist(parent.to_string()));
+            }
+        }
+        let roots: Vec<String> = roots
+            .into_iter()
+            .filter(|id| comments.contains_key(id))
+            .collect();
 
-        Ok(comments)
+        let depth = query
+            .depth
+            .unwrap_or(COMMENT_TREE_DEPTH)
+            .min(COMMENT_TREE_MAX_DEPTH);
+        let limit = query
+            .limit
+            .unwrap_or(COMMENT_PAGE_LENGTH)
+            .clamp(1, COMMENT_MAX_PAGE_LENGTH);
+        let total = roots.len();
+        let roots = sort_comment_ids(&comments, roots, query.sort);
+
+        let nodes = roots
+            .iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(limit)
+            .filter_map(|id| build_comment_node(&mut comments, id, depth, limit, query.sort))
+            .collect();
+
+        Ok((total, nodes))
     }
 }
 
@@ -244,3 +807,268 @@ async fn filter_empty_field<'a>(
 
     Ok(new_comment)
 }
+
+// Likely spam is held whatever the mode, `has_approved` tells if the user already got a comment
+// approved
+fn moderation_status(
+    spam_score: f64,
+    spam_threshold: f64,
+    mode: ModerationMode,
+    has_approved: bool,
+) -> CommentStatus {
+    if spam_score >= spam_threshold {
+        return CommentStatus::Pending;
+    }
+
+    match mode {
+        ModerationMode::Open => CommentStatus::Approved,
+        ModerationMode::FirstTime if has_approved => CommentStatus::Approved,
+        _ => CommentStatus::Pending,
+    }
+}
+
+fn sort_comment_ids(
+    comments: &HashMap<String, Comment>,
+    mut ids: Vec<String>,
+    sort: CommentSort,
+) -> Vec<String> {
+    let created_at = |id: &String| comments.get(id).map(|comment| comment.created_at);
+    let like_count = |id: &String| {
+        comments
+            .get(id)
+            .map(|comment| comment.like_count)
+            .unwrap_or(0)
+    };
+    match sort {
+        CommentSort::Oldest => ids.sort_by_key(created_at),
+        CommentSort::Newest => ids.sort_by_key(|id| std::cmp::Reverse(created_at(id))),
+        CommentSort::MostLiked => {
+            ids.sort_by_key(|id| (std::cmp::Reverse(like_count(id)), created_at(id)))
+        }
+    }
+
+    ids
+}
+
+// Comments are taken out of the map as they are placed, so a broken `reply` list can not loop
+fn build_comment_node(
+    comments: &mut HashMap<String, Comment>,
+    id: &str,
+    depth: usize,
+    limit: usize,
+    sort: CommentSort,
+) -> Option<CommentNode> {
+    let comment = comments.remove(id)?;
+    let reply_ids: Vec<String> = comment
+        .reply
+        .iter()
+        .flatten()
+        .map(|id| id.to_string())
+        .filter(|id| comments.contains_key(id))
+        .collect();
+    let reply_count = reply_ids.len();
+
+    let replies: Vec<CommentNode> = if depth == 0 {
+        Vec::new()
+    } else {
+        sort_comment_ids(comments, reply_ids, sort)
+            .iter()
+            .take(limit)
+            .filter_map(|id| build_comment_node(comments, id, depth - 1, limit, sort))
+            .collect()
+    };
+
+    Some(CommentNode {
+        reply_count,
+        has_more_replies: replies.len() < reply_count,
+        replies,
+        comment: CommentEntry::from(comment),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::spam::SpamCounts;
+
+    // Comments of `article:one` by `user:author`, with `fields` set on top
+    async fn create_comment(database: &Database, id: &str, fields: &str) {
+        let sql = format!(
+            "CREATE comment:{} SET user_id = user:author, article_id = article:one, \
+             deleted = false, created_at = time::now(), {}",
+            id, fields
+        );
+        database.client.query(sql).await.unwrap();
+    }
+
+    fn node_id(node: &CommentNode) -> String {
+        match &node.comment {
+            CommentEntry::Comment(comment) => comment.id.id.to_raw(),
+            CommentEntry::Tombstone(tombstone) => tombstone.id.id.to_raw(),
+        }
+    }
+
+    #[test]
+    fn moderation_status_holds_likely_spam_whatever_the_mode() {
+        assert_eq!(
+            moderation_status(0.95, 0.9, ModerationMode::Open, true),
+            CommentStatus::Pending
+        );
+        assert_eq!(
+            moderation_status(0.2, 0.9, ModerationMode::Open, false),
+            CommentStatus::Approved
+        );
+        assert_eq!(
+            moderation_status(0.2, 0.9, ModerationMode::FirstTime, false),
+            CommentStatus::Pending
+        );
+        assert_eq!(
+            moderation_status(0.2, 0.9, ModerationMode::FirstTime, true),
+            CommentStatus::Approved
+        );
+        assert_eq!(
+            moderation_status(0.2, 0.9, ModerationMode::Always, true),
+            CommentStatus::Pending
+        );
+    }
+
+    #[tokio::test]
+    async fn set_comment_status_trains_the_spam_filter() {
+        let database = Database::start_in_memory().await;
+        let admin = Context {
+            user_id: Thing::from(("user", "admin")),
+            user_role: Role::Admin,
+        };
+        let mut spam = Vec::new();
+        let mut ham = Vec::new();
+        for index in 0..5 {
+            let id = format!("spam{}", index);
+            create_comment(
+                &database,
+                &id,
+                "content = 'Cheap pills', status = 'pending'",
+            )
+            .await;
+            spam.push(Thing::from((COMMENT_TBL_NAME, id.as_str())));
+            let id = format!("ham{}", index);
+            create_comment(
+                &database,
+                &id,
+                "content = 'Lovely prose', status = 'pending'",
+            )
+            .await;
+            ham.push(Thing::from((COMMENT_TBL_NAME, id.as_str())));
+        }
+
+        database
+            .set_comment_status(&admin, &spam, CommentStatus::Spam)
+            .await
+            .unwrap();
+        database
+            .set_comment_status(&admin, &ham, CommentStatus::Approved)
+            .await
+            .unwrap();
+
+        let comment = database.get_comment(&spam[0]).await.unwrap();
+        assert_eq!(comment.status, CommentStatus::Spam);
+        assert_eq!(comment.spam_label, Some(SpamLabel::Spam));
+        assert!(database.get_spam_score("cheap pills", &[]).await.unwrap() >= 0.9);
+        assert!(database.get_spam_score("lovely prose", &[]).await.unwrap() < 0.1);
+
+        // Approving a comment marked as spam moves it to the other class
+        database
+            .set_comment_status(&admin, &spam[..1], CommentStatus::Approved)
+            .await
+            .unwrap();
+        let comment = database.get_comment(&spam[0]).await.unwrap();
+        assert_eq!(comment.spam_label, Some(SpamLabel::Ham));
+        let counts: Option<SpamCounts> = database
+            .client
+            .query("SELECT spam, ham FROM spam_token:pill")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(counts.map(|counts| (counts.spam, counts.ham)), Some((4, 1)));
+    }
+
+    #[tokio::test]
+    async fn delete_comment_keeps_the_revisions() {
+        let database = Database::start_in_memory().await;
+        create_comment(&database, "one", "content = 'Edited', status = 'approved'").await;
+        database
+            .client
+            .query(
+                "CREATE comment_revision SET comment_id = comment:one, content = 'First', \
+                 created_at = time::now(), replaced_at = time::now()",
+            )
+            .await
+            .unwrap();
+        let id = Thing::from((COMMENT_TBL_NAME, "one"));
+
+        let comment = database.get_comment(&id).await.unwrap();
+        database.delete_comment(&comment).await.unwrap();
+
+        assert!(database.get_comment(&id).await.unwrap().deleted);
+        let revisions = database.list_comment_revisions(&id).await.unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content.as_deref(), Some("First"));
+    }
+
+    #[tokio::test]
+    async fn comment_tree_keeps_replies_to_held_comments_out_of_the_roots() {
+        let database = Database::start_in_memory().await;
+        create_comment(
+            &database,
+            "pending",
+            "status = 'pending', reply = [comment:reply]",
+        )
+        .await;
+        create_comment(&database, "reply", "status = 'approved'").await;
+        create_comment(&database, "top", "status = 'approved'").await;
+        let article_id = Thing::from(("article", "one"));
+
+        let (total, nodes) = database
+            .get_comment_tree(&article_id, None, &CommentQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(nodes.iter().map(node_id).collect::<Vec<String>>(), ["top"]);
+
+        let pending = Thing::from((COMMENT_TBL_NAME, "pending"));
+        assert!(matches!(
+            database
+                .get_comment_tree(&article_id, Some(&pending), &CommentQuery::default())
+                .await,
+            Err(Error::DBRecordDidNotExist(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn comment_tree_drops_the_replies_of_hidden_comments() {
+        let database = Database::start_in_memory().await;
+        create_comment(
+            &database,
+            "top",
+            "status = 'approved', reply = [comment:reported]",
+        )
+        .await;
+        create_comment(
+            &database,
+            "reported",
+            "status = 'approved', hidden = true, reply = [comment:below]",
+        )
+        .await;
+        create_comment(&database, "below", "status = 'approved'").await;
+        let article_id = Thing::from(("article", "one"));
+
+        let (total, nodes) = database
+            .get_comment_tree(&article_id, None, &CommentQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(nodes.iter().map(node_id).collect::<Vec<String>>(), ["top"]);
+        assert_eq!(nodes[0].reply_count, 0);
+        assert!(nodes[0].replies.is_empty());
+    }
+}
@@ -5,6 +5,10 @@ impl Database {
     pub async fn create_events(&self) -> Result<(), Error> {
         self.on_create_article().await?;
         self.on_create_comment().await?;
+        self.on_like().await?;
+        self.on_unlike().await?;
+        self.on_react().await?;
+        self.on_unreact().await?;
 
         Ok(())
     }
@@ -46,4 +50,74 @@ impl Database {
 
         Ok(())
     }
+
+    // `like_count` follows the `likes` relations of the article or the comment
+    async fn on_like(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE EVENT on_like ON TABLE likes WHEN $event = "CREATE" THEN {
+                LET $target = $after.out;
+
+                UPDATE $target SET like_count += 1;
+            };
+        "#;
+        self.client
+            .query(sql)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateEvent(err.to_string()))?;
+        log::info!("Successfully create event: `on_like`");
+
+        Ok(())
+    }
+
+    async fn on_unlike(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE EVENT on_unlike ON TABLE likes WHEN $event = "DELETE" THEN {
+                LET $target = $before.out;
+
+                UPDATE $target SET like_count -= 1;
+            };
+        "#;
+        self.client
+            .query(sql)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateEvent(err.to_string()))?;
+        log::info!("Successfully create event: `on_unlike`");
+
+        Ok(())
+    }
+
+    // `reactions` holds the count of every reaction the article or the comment got
+    async fn on_react(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE EVENT on_react ON TABLE reacts WHEN $event = "CREATE" THEN {
+                LET $target = $after.out;
+
+                UPDATE $target SET reactions = (SELECT reaction, count() AS count FROM reacts WHERE out = $target GROUP BY reaction);
+            };
+        "#;
+        self.client
+            .query(sql)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateEvent(err.to_string()))?;
+        log::info!("Successfully create event: `on_react`");
+
+        Ok(())
+    }
+
+    async fn on_unreact(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE EVENT on_unreact ON TABLE reacts WHEN $event = "DELETE" THEN {
+                LET $target = $before.out;
+
+                UPDATE $target SET reactions = (SELECT reaction, count() AS count FROM reacts WHERE out = $target GROUP BY reaction);
+            };
+        "#;
+        self.client
+            .query(sql)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateEvent(err.to_string()))?;
+        log::info!("Successfully create event: `on_unreact`");
+
+        Ok(())
+    }
 }
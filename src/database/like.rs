@@ -1,12 +1,31 @@
 use crate::database::{article::ARTICLE_TBL_NAME, comment::COMMENT_TBL_NAME, Database};
 use crate::errors::Error;
-use crate::models::{article::Article, comment::Comment};
+use crate::models::{
+    article::Article,
+    comment::Comment,
+    like::{LikeQuery, LikedContent, LikedItem, Liker},
+};
+use crate::s3;
 use crate::server::context::Context;
-use crate::utils::OpChanges;
 
-use surrealdb::{opt::PatchOp, sql::Thing};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use surrealdb::sql::Thing;
 
 const LIKE_TBL_NAME: &str = "likes";
+const LIKE_PAGE_LENGTH: usize = 20;
+const LIKE_MAX_PAGE_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct LikeCount {
+    total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct LikeEdge {
+    out: Thing,
+    created_at: Option<DateTime<Utc>>,
+}
 
 impl Database {
     pub async fn create_like_table(&self) -> Result<(), Error> {
@@ -24,43 +43,39 @@ impl Database {
         Ok(())
     }
 
+    // `like_count` of the article or the comment is updated by the `on_like` event
     pub async fn like_comment_or_article(
         &self,
         context: &Context,
         id: &Thing,
     ) -> Result<Thing, Error> {
-        let sql = format!("RELATE {}->likes->{}", context.user_id, id);
-        let mut response = self.client.query(sql).await.map_err(|err| {
-            Error::DBCouldNotRelateRecord(
-                context.user_id.to_string(),
-                id.to_string(),
-                err.to_string(),
-            )
-        })?;
-        let like: Option<Thing> = response
-            .take("id")
-            .map_err(|err| Error::DBRecordAlreadyExist(id.to_string(), err.to_string()))?;
-        let changes: Vec<OpChanges> = self
+        let sql = format!(
+            "RELATE $user->{}->$target SET created_at = time::now()",
+            LIKE_TBL_NAME
+        );
+        let mut response = self
             .client
-            .update((id.tb.clone(), id.id.clone()))
-            .patch(PatchOp::add("/liked_by", [context.user_id.clone()]))
+            .query(sql)
+            .bind(("user", &context.user_id))
+            .bind(("target", id))
             .await
+            .map_err(|err| {
+                Error::DBCouldNotRelateRecord(
+                    context.user_id.to_string(),
+                    id.to_string(),
+                    err.to_string(),
+                )
+            })?;
+        let like: Option<Thing> = response
+            .take("id")
             .map_err(|err| Error::DBRecordAlreadyExist(id.to_string(), err.to_string()))?;
-        log::debug!(
-            "Successfully add like to: `{}`, changes: {:?}",
-            &id,
-            changes
-        );
+        log::debug!("Successfully add like to: `{}`", &id);
 
-        if let Some(like) = like {
-            Ok(like)
-        } else {
-            Err(Error::DBCouldNotRelateRecord(
-                context.user_id.to_string(),
-                id.to_string(),
-                "".to_string(),
-            ))
-        }
+        like.ok_or(Error::DBCouldNotRelateRecord(
+            context.user_id.to_string(),
+            id.to_string(),
+            "".to_string(),
+        ))
     }
 
     pub async fn unlike_comment_or_article(
@@ -69,64 +84,159 @@ impl Database {
         id: &Thing,
     ) -> Result<(), Error> {
         let sql = format!(
-            "DELETE likes WHERE in = {} AND out = {}",
-            context.user_id, id
+            "DELETE {} WHERE in = $user AND out = $target RETURN BEFORE",
+            LIKE_TBL_NAME
         );
-        self.client.query(sql).await.map_err(|err| {
-            Error::DBCouldNotDeleteRelateRecord(
-                context.user_id.to_string(),
-                id.to_string(),
-                err.to_string(),
-            )
-        })?;
-        let mut position: Option<usize> = Default::default();
+        let removed: Vec<Thing> = self
+            .client
+            .query(sql)
+            .bind(("user", &context.user_id))
+            .bind(("target", id))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotDeleteRelateRecord(
+                    context.user_id.to_string(),
+                    id.to_string(),
+                    err.to_string(),
+                )
+            })?
+            .take("id")
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        if removed.is_empty() {
+            return Err(Error::DBRecordDidNotExist(context.user_id.to_string()));
+        }
+        log::debug!("Successfully remove like to: `{}`", &id);
 
-        if id.tb == ARTICLE_TBL_NAME {
-            let article: Article = self
-                .client
-                .select((id.tb.clone(), id.id.clone()))
-                .await
-                .map_err(|err| Error::DBCouldNotSelectRecord(id.to_string(), err.to_string()))?;
+        Ok(())
+    }
 
-            if let Some(user_ids) = article.liked_by {
-                position = user_ids
-                    .iter()
-                    .position(|element| element == &context.user_id);
-            } else {
-                return Err(Error::DBRecordDidNotExist(context.user_id.to_string()));
+    // Most recent first, deleted users are left out
+    pub async fn list_likers(
+        &self,
+        target: &Thing,
+        query: &LikeQuery,
+    ) -> Result<(usize, Vec<Liker>), Error> {
+        self.check_content_is_public(target, true).await?;
+
+        let filter = "out = $target AND in.deleted = false";
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE {} GROUP ALL; SELECT in.id AS id, in.username AS username, in.first_name AS first_name, in.last_name AS last_name, in.profile_pic_uri AS avatar, created_at AS liked_at FROM {} WHERE {} ORDER BY liked_at DESC LIMIT {} START {};",
+            LIKE_TBL_NAME,
+            filter,
+            LIKE_TBL_NAME,
+            filter,
+            page_length(query),
+            query.offset.unwrap_or(0)
+        );
+        let mut response = self
+            .client
+            .query(sql)
+            .bind(("target", target))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(target.to_string(), err.to_string()))?;
+        let total: Option<LikeCount> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let mut likers: Vec<Liker> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        // Only the path in the bucket is stored
+        for liker in &mut likers {
+            if let Some(avatar) = &liker.avatar {
+                liker.avatar = Some(s3::get_object_url(avatar)?);
             }
-        } else if id.tb == COMMENT_TBL_NAME {
-            let comment: Comment = self
-                .client
-                .select((id.tb.clone(), id.id.clone()))
-                .await
-                .map_err(|err| Error::DBCouldNotSelectRecord(id.to_string(), err.to_string()))?;
-
-            if let Some(user_ids) = comment.liked_by {
-                position = user_ids
+        }
+
+        Ok((total.map(|total| total.total).unwrap_or(0), likers))
+    }
+
+    // Most recent first, only what readers can see is listed
+    pub async fn list_likes_for_user(
+        &self,
+        user: &Thing,
+        query: &LikeQuery,
+    ) -> Result<(usize, Vec<LikedItem>), Error> {
+        let mut filters = vec![
+            "in = $user",
+            "((meta::tb(out) = $article_tb AND out.public = true AND out.unlisted != true AND out.hidden != true) OR (meta::tb(out) = $comment_tb AND out.status = 'approved' AND out.hidden != true AND out.deleted = false AND out.article_id.unlisted != true))",
+        ];
+        if query.kind.is_some() {
+            filters.push("meta::tb(out) = $kind");
+        }
+        let filter = filters.join(" AND ");
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE {} GROUP ALL; SELECT out, created_at FROM {} WHERE {} ORDER BY created_at DESC LIMIT {} START {};",
+            LIKE_TBL_NAME,
+            filter,
+            LIKE_TBL_NAME,
+            filter,
+            page_length(query),
+            query.offset.unwrap_or(0)
+        );
+        let mut response = self
+            .client
+            .query(sql)
+            .bind(("user", user))
+            .bind(("article_tb", ARTICLE_TBL_NAME))
+            .bind(("comment_tb", COMMENT_TBL_NAME))
+            .bind(("kind", &query.kind))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(user.to_string(), err.to_string()))?;
+        let total: Option<LikeCount> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let edges: Vec<LikeEdge> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        let ids: Vec<&Thing> = edges.iter().map(|edge| &edge.out).collect();
+        let sql = format!(
+            "SELECT * FROM {} WHERE id INSIDE $ids; SELECT * FROM {} WHERE id INSIDE $ids;",
+            ARTICLE_TBL_NAME, COMMENT_TBL_NAME
+        );
+        let mut response = self
+            .client
+            .query(sql)
+            .bind(("ids", &ids))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(user.to_string(), err.to_string()))?;
+        let mut articles: Vec<Article> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let mut comments: Vec<Comment> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        // Back in the order of the likes
+        let mut likes = Vec::with_capacity(edges.len());
+        for edge in edges {
+            let content = if edge.out.tb == ARTICLE_TBL_NAME {
+                articles
                     .iter()
-                    .position(|element| element == &context.user_id);
+                    .position(|article| article.id == edge.out)
+                    .map(|position| LikedContent::Article(articles.swap_remove(position)))
             } else {
-                return Err(Error::DBRecordDidNotExist(context.user_id.to_string()));
+                comments
+                    .iter()
+                    .position(|comment| comment.id == edge.out)
+                    .map(|position| LikedContent::Comment(comments.swap_remove(position)))
+            };
+            if let Some(content) = content {
+                likes.push(LikedItem {
+                    content,
+                    liked_at: edge.created_at,
+                });
             }
         }
 
-        if let Some(position) = position {
-            let changes: Vec<OpChanges> = self
-                .client
-                .update((id.tb.clone(), id.id.clone()))
-                .patch(PatchOp::remove(format!("/liked_by/{}", position).as_str()))
-                .await
-                .map_err(|err| Error::DBCouldNotSelectRecord(id.to_string(), err.to_string()))?;
-            log::debug!(
-                "Successfully remove like to: `{}`, changes: {:?}",
-                &id,
-                changes
-            );
-
-            Ok(())
-        } else {
-            Err(Error::DBRecordDidNotExist(context.user_id.to_string()))
-        }
+        Ok((total.map(|total| total.total).unwrap_or(0), likes))
     }
 }
+
+fn page_length(query: &LikeQuery) -> usize {
+    query
+        .limit
+        .unwrap_or(LIKE_PAGE_LENGTH)
+        .clamp(1, LIKE_MAX_PAGE_LENGTH)
+}
@@ -0,0 +1,196 @@
+use crate::database::{
+    user::{BLOCK_TBL_NAME, USER_TBL_NAME},
+    Database,
+};
+use crate::errors::Error;
+use crate::models::{
+    article::Article,
+    comment::{Comment, CommentStatus},
+    notification::NotificationKind,
+    user::User,
+};
+use crate::s3;
+use crate::server::config::SiteConfig;
+use crate::utils::{html, markdown, mention};
+
+use std::collections::HashMap;
+use surrealdb::sql::Thing;
+
+pub const MENTION_TBL_NAME: &str = "mentions";
+
+impl Database {
+    pub async fn create_mention_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE INDEX unique_mentions
+            ON TABLE mentions
+            COLUMNS in, out UNIQUE;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(MENTION_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", MENTION_TBL_NAME);
+
+        Ok(())
+    }
+
+    // What `content_html` of a comment written by `author` holds, mentions link to profiles
+    pub async fn render_comment_content(
+        &self,
+        author: &Thing,
+        content: Option<&str>,
+    ) -> Result<String, Error> {
+        let content = content.unwrap_or_default();
+        let users = self
+            .resolve_mentions(author, &mention::extract_mentions(content))
+            .await?;
+
+        Ok(markdown::render_comment(content, &profile_urls(&users)?))
+    }
+
+    // Mentioned users are only notified once the comment is published
+    pub async fn process_comment_mentions(&self, comment: &Comment) -> Result<(), Error> {
+        let content = comment.content.clone().unwrap_or_default();
+        let users = self
+            .resolve_mentions(&comment.user_id, &mention::extract_mentions(&content))
+            .await?;
+
+        if comment.status == CommentStatus::Approved && !comment.deleted && !comment.hidden {
+            self.save_mentions(&comment.id, &comment.user_id, &comment.article_id, &users)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Links the mentions in the body and uploads it again when that changed it, returns the
+    // body as stored. Mentions of drafts are not saved, so publishing notifies everyone mentioned
+    pub async fn process_article_mentions(
+        &self,
+        article: &Article,
+        content: &str,
+    ) -> Result<String, Error> {
+        let users = self
+            .resolve_mentions(&article.user_id, &mention::extract_mentions(content))
+            .await?;
+        let linked = mention::link_mentions(content, &profile_urls(&users)?);
+        if linked != content {
+            let content_type = if html::is_html(&linked) {
+                "text/html"
+            } else {
+                "text/markdown"
+            };
+            s3::get_bucket()
+                .await?
+                .put_object_with_content_type(&article.article_uri, linked.as_bytes(), content_type)
+                .await
+                .map_err(|err| Error::MinioCouldNotPutObject(err.to_string()))?;
+        }
+
+        if article.public {
+            self.save_mentions(&article.id, &article.user_id, &article.id, &users)
+                .await?;
+        }
+
+        Ok(linked)
+    }
+
+    // Deleted users, users who blocked the author and users blocked by the author are ignored
+    async fn resolve_mentions(
+        &self,
+        author: &Thing,
+        usernames: &[String],
+    ) -> Result<Vec<User>, Error> {
+        if usernames.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let sql = format!(
+            "SELECT * FROM {} WHERE username INSIDE $usernames AND deleted = false AND id != $author AND id NOTINSIDE (SELECT VALUE out FROM {} WHERE in = $author) AND id NOTINSIDE (SELECT VALUE in FROM {} WHERE out = $author)",
+            USER_TBL_NAME, BLOCK_TBL_NAME, BLOCK_TBL_NAME
+        );
+        let users: Vec<User> = self
+            .client
+            .query(sql)
+            .bind(("usernames", usernames))
+            .bind(("author", author))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(author.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(users)
+    }
+
+    // Replaces the `mentions` relations of `source`, only users who were not mentioned by an
+    // earlier published version get notified
+    async fn save_mentions(
+        &self,
+        source: &Thing,
+        author: &Thing,
+        article_id: &Thing,
+        users: &[User],
+    ) -> Result<(), Error> {
+        let ids: Vec<Thing> = users.iter().map(|user| user.id.clone()).collect();
+        let sql = format!(
+            "SELECT VALUE out FROM {} WHERE in = $source; DELETE {} WHERE in = $source AND out NOTINSIDE $ids;",
+            MENTION_TBL_NAME, MENTION_TBL_NAME
+        );
+        let mentioned: Vec<Thing> = self
+            .client
+            .query(sql)
+            .bind(("source", source))
+            .bind(("ids", &ids))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(source.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        let added: Vec<Thing> = ids
+            .into_iter()
+            .filter(|id| !mentioned.contains(id))
+            .collect();
+        for user in &added {
+            let sql = format!(
+                "RELATE $source->{}->$user SET created_at = time::now()",
+                MENTION_TBL_NAME
+            );
+            self.client
+                .query(sql)
+                .bind(("source", source))
+                .bind(("user", user))
+                .await
+                .map_err(|err| {
+                    Error::DBCouldNotRelateRecord(
+                        source.to_string(),
+                        user.to_string(),
+                        err.to_string(),
+                    )
+                })?;
+        }
+        log::debug!(
+            "Successfully saved mentions of: `{}`, new: {:?}",
+            source,
+            added
+        );
+
+        for user in added {
+            self.create_notification(&user, NotificationKind::Mention, author, source, article_id)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn profile_urls(users: &[User]) -> Result<HashMap<String, String>, Error> {
+    if users.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let site = SiteConfig::parse_from_env_file()?;
+
+    Ok(users
+        .iter()
+        .map(|user| (user.username.clone(), site.author_url(&user.username)))
+        .collect())
+}
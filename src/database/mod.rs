@@ -3,24 +3,46 @@ pub mod comment;
 pub mod config;
 pub mod event;
 pub mod like;
+pub mod mention;
+pub mod notification;
+pub mod reaction;
+pub mod report;
+pub mod search;
+pub mod series;
+pub mod spam;
+pub mod tag;
 pub mod user;
 
 use crate::database::config::DatabaseConfig;
 use crate::errors::Error;
+use crate::models::{article::RelatedArticle, search::Suggestion};
+use crate::utils::cache::Cache;
+
+use std::time::Duration;
 use surrealdb::{
-    engine::remote::ws::{Client, Ws},
+    engine::any::{self, Any},
     opt::auth::Root,
     Surreal,
 };
 
+const SUGGESTION_CACHE_TTL: Duration = Duration::from_secs(60);
+const SUGGESTION_CACHE_CAPACITY: usize = 1000;
+const RELATED_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+const RELATED_CACHE_CAPACITY: usize = 10_000;
+
 pub struct Database {
-    client: Surreal<Client>,
+    client: Surreal<Any>,
+    suggestions: Cache<Vec<Suggestion>>,
+    // Emptied whenever an article changes, since it can affect every other list
+    related: Cache<Vec<RelatedArticle>>,
 }
 
 impl Database {
     pub fn new() -> Self {
         Database {
             client: Surreal::init(),
+            suggestions: Cache::new(SUGGESTION_CACHE_TTL, SUGGESTION_CACHE_CAPACITY),
+            related: Cache::new(RELATED_CACHE_TTL, RELATED_CACHE_CAPACITY),
         }
     }
 
@@ -31,7 +53,7 @@ impl Database {
             "Connecting to database server at: http://{}",
             config.address
         );
-        self.client = Surreal::new::<Ws>(config.address.clone())
+        self.client = any::connect(format!("ws://{}", config.address))
             .await
             .map_err(|error| Error::DBCouldNotOpenWebSocket(config.address, error.to_string()))?;
         log::info!("Successfully connected to database server");
@@ -64,9 +86,33 @@ impl Database {
     async fn create_all_table(&self) -> Result<(), Error> {
         self.create_user_table().await?;
         self.create_comment_table().await?;
+        self.create_comment_revision_table().await?;
         self.create_article_table().await?;
         self.create_like_table().await?;
+        self.create_reaction_table().await?;
+        self.create_tag_table().await?;
+        self.create_series_table().await?;
+        self.create_search_table().await?;
+        self.create_spam_table().await?;
+        self.create_report_table().await?;
+        self.create_mention_table().await?;
+        self.create_block_table().await?;
+        self.create_notification_table().await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+impl Database {
+    // Empty database with every table and event, held in memory for the tests
+    pub async fn start_in_memory() -> Self {
+        let mut database = Database::new();
+        database.client = any::connect("mem://").await.unwrap();
+        database.client.use_ns("test").use_db("test").await.unwrap();
+        database.create_all_table().await.unwrap();
+        database.create_events().await.unwrap();
+
+        database
+    }
+}
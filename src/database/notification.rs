@@ -0,0 +1,142 @@
+use crate::database::Database;
+use crate::errors::Error;
+use crate::models::notification::{
+    Notification, NotificationForCreate, NotificationKind, NotificationQuery,
+};
+
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+pub const NOTIFICATION_TBL_NAME: &str = "notification";
+const NOTIFICATION_PAGE_LENGTH: usize = 20;
+const NOTIFICATION_MAX_PAGE_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct NotificationCount {
+    total: usize,
+}
+
+impl Database {
+    pub async fn create_notification_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE TABLE notification SCHEMAFULL;
+            DEFINE FIELD user_id            ON TABLE notification TYPE record(user)              ASSERT $value != NONE;
+            DEFINE FIELD kind               ON TABLE notification TYPE string                    ASSERT $value INSIDE ["mention"];
+            DEFINE FIELD actor_id           ON TABLE notification TYPE record(user)              ASSERT $value != NONE;
+            DEFINE FIELD source             ON TABLE notification TYPE record(comment, article)  ASSERT $value != NONE;
+            DEFINE FIELD article_id         ON TABLE notification TYPE record(article)           ASSERT $value != NONE;
+            DEFINE FIELD read               ON TABLE notification TYPE bool                      ASSERT $value != NONE;
+            DEFINE FIELD created_at         ON TABLE notification TYPE datetime                  ASSERT $value != NONE;
+            DEFINE INDEX notification_user  ON TABLE notification COLUMNS user_id;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(NOTIFICATION_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", NOTIFICATION_TBL_NAME);
+
+        Ok(())
+    }
+
+    pub async fn create_notification(
+        &self,
+        user: &Thing,
+        kind: NotificationKind,
+        actor: &Thing,
+        source: &Thing,
+        article_id: &Thing,
+    ) -> Result<(), Error> {
+        let info = NotificationForCreate {
+            user_id: user.clone(),
+            kind,
+            actor_id: actor.clone(),
+            source: source.clone(),
+            article_id: article_id.clone(),
+            read: false,
+            created_at: chrono::offset::Utc::now(),
+        };
+        let notification: Notification = self
+            .client
+            .create(NOTIFICATION_TBL_NAME)
+            .content(&info)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateRecord(err.to_string()))?;
+        log::debug!(
+            "Successfully notified: `{}` with: `{}`",
+            user,
+            notification.id
+        );
+
+        Ok(())
+    }
+
+    // Newest first, along with the number of matching and of unread notifications
+    pub async fn list_notifications(
+        &self,
+        user: &Thing,
+        query: &NotificationQuery,
+    ) -> Result<(usize, usize, Vec<Notification>), Error> {
+        let filter = if query.unread {
+            "user_id = $user AND read = false"
+        } else {
+            "user_id = $user"
+        };
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE {} GROUP ALL; SELECT count() AS total FROM {} WHERE user_id = $user AND read = false GROUP ALL; SELECT * FROM {} WHERE {} ORDER BY created_at DESC LIMIT {} START {};",
+            NOTIFICATION_TBL_NAME,
+            filter,
+            NOTIFICATION_TBL_NAME,
+            NOTIFICATION_TBL_NAME,
+            filter,
+            query
+                .limit
+                .unwrap_or(NOTIFICATION_PAGE_LENGTH)
+                .clamp(1, NOTIFICATION_MAX_PAGE_LENGTH),
+            query.offset.unwrap_or(0)
+        );
+
+        let mut response = self
+            .client
+            .query(sql)
+            .bind(("user", user))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(user.to_string(), err.to_string()))?;
+        let total: Option<NotificationCount> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let unread: Option<NotificationCount> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let notifications: Vec<Notification> = response
+            .take(2)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok((
+            total.map(|total| total.total).unwrap_or(0),
+            unread.map(|unread| unread.total).unwrap_or(0),
+            notifications,
+        ))
+    }
+
+    // Every notification of the user when `ids` is empty
+    pub async fn mark_notifications_read(&self, user: &Thing, ids: &[Thing]) -> Result<(), Error> {
+        let filter = if ids.is_empty() {
+            "user_id = $user AND read = false"
+        } else {
+            "user_id = $user AND read = false AND id INSIDE $ids"
+        };
+        let sql = format!(
+            "UPDATE {} SET read = true WHERE {}",
+            NOTIFICATION_TBL_NAME, filter
+        );
+        self.client
+            .query(sql)
+            .bind(("user", user))
+            .bind(("ids", ids))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(user.to_string(), err.to_string()))?;
+        log::debug!("Successfully marked notifications of: `{}` as read", user);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::database::{article::ARTICLE_TBL_NAME, comment::COMMENT_TBL_NAME, Database};
+use crate::errors::Error;
+use crate::models::comment::CommentStatus;
+use crate::server::{config::SiteConfig, context::Context};
+
+use surrealdb::sql::Thing;
+
+// `in` reacted to `out` with `reaction`
+pub const REACTION_TBL_NAME: &str = "reacts";
+
+impl Database {
+    pub async fn create_reaction_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE INDEX unique_reactions
+            ON TABLE reacts
+            COLUMNS in, out, reaction UNIQUE;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(REACTION_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", REACTION_TBL_NAME);
+
+        Ok(())
+    }
+
+    // A user can react once with each reaction of the site
+    pub async fn add_reaction(
+        &self,
+        context: &Context,
+        target: &Thing,
+        reaction: &str,
+    ) -> Result<Thing, Error> {
+        check_reaction(reaction)?;
+        self.check_content_is_public(target, false).await?;
+
+        let sql = format!(
+            "RELATE $user->{}->$target SET reaction = $reaction, created_at = time::now()",
+            REACTION_TBL_NAME
+        );
+        let mut response = self
+            .client
+            .query(sql)
+            .bind(("user", &context.user_id))
+            .bind(("target", target))
+            .bind(("reaction", reaction))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotRelateRecord(
+                    context.user_id.to_string(),
+                    target.to_string(),
+                    err.to_string(),
+                )
+            })?;
+        let id: Option<Thing> = response
+            .take("id")
+            .map_err(|err| Error::DBRecordAlreadyExist(target.to_string(), err.to_string()))?;
+        let id = id.ok_or(Error::DBCouldNotRelateRecord(
+            context.user_id.to_string(),
+            target.to_string(),
+            String::from(""),
+        ))?;
+        log::debug!("Successfully add reaction `{}` to: `{}`", reaction, target);
+
+        Ok(id)
+    }
+
+    pub async fn remove_reaction(
+        &self,
+        context: &Context,
+        target: &Thing,
+        reaction: &str,
+    ) -> Result<(), Error> {
+        let sql = format!(
+            "DELETE {} WHERE in = $user AND out = $target AND reaction = $reaction RETURN BEFORE",
+            REACTION_TBL_NAME
+        );
+        let removed: Vec<Thing> = self
+            .client
+            .query(sql)
+            .bind(("user", &context.user_id))
+            .bind(("target", target))
+            .bind(("reaction", reaction))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotDeleteRelateRecord(
+                    context.user_id.to_string(),
+                    target.to_string(),
+                    err.to_string(),
+                )
+            })?
+            .take("id")
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        if removed.is_empty() {
+            return Err(Error::DBRecordDidNotExist(format!(
+                "{} on {}",
+                reaction, target
+            )));
+        }
+        log::debug!(
+            "Successfully remove reaction `{}` from: `{}`",
+            reaction,
+            target
+        );
+
+        Ok(())
+    }
+
+    // Articles and comments that readers can not see are reported as missing. Unlisted articles
+    // can be read from their link, `listed_only` also leaves them out
+    pub async fn check_content_is_public(
+        &self,
+        target: &Thing,
+        listed_only: bool,
+    ) -> Result<(), Error> {
+        let visible = match target.tb.as_str() {
+            COMMENT_TBL_NAME => {
+                let comment = self.get_comment(target).await?;
+                !comment.deleted && !comment.hidden && comment.status == CommentStatus::Approved
+            }
+            ARTICLE_TBL_NAME => {
+                let article = self.get_article_with_id(target).await?;
+                article.public && !article.hidden && !(listed_only && article.unlisted)
+            }
+            _ => false,
+        };
+        if !visible {
+            return Err(Error::DBRecordDidNotExist(target.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+fn check_reaction(reaction: &str) -> Result<(), Error> {
+    let reactions = SiteConfig::parse_from_env_file()?.reactions;
+    if !reactions.iter().any(|element| element == reaction) {
+        return Err(Error::ServerBadRequest(format!(
+            "Reaction must be one of: {}",
+            reactions.join(" ")
+        )));
+    }
+
+    Ok(())
+}
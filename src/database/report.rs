@@ -0,0 +1,333 @@
+use crate::database::{
+    article::ARTICLE_TBL_NAME, comment::COMMENT_TBL_NAME, user::USER_TBL_NAME, Database,
+};
+use crate::errors::Error;
+use crate::models::report::{
+    Report, ReportAction, ReportForCreate, ReportQuery, ReportResolution, ReportStatus,
+};
+use crate::server::{config::SiteConfig, context::Context};
+use crate::utils::OpChanges;
+
+use serde::Deserialize;
+use surrealdb::{opt::PatchOp, sql::Thing};
+
+pub const REPORT_TBL_NAME: &str = "report";
+const REPORT_PAGE_LENGTH: usize = 50;
+const REPORT_MAX_PAGE_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct ReportCount {
+    total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReportId {
+    id: Thing,
+}
+
+impl Database {
+    pub async fn create_report_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE TABLE report SCHEMAFULL;
+            DEFINE FIELD reporter_id        ON TABLE report TYPE record(user)                   ASSERT $value != NONE;
+            DEFINE FIELD target             ON TABLE report TYPE record(comment, article, user) ASSERT $value != NONE;
+            DEFINE FIELD reason             ON TABLE report TYPE string                         ASSERT $value INSIDE ["spam", "harassment", "hate_speech", "violence", "sexual_content", "misinformation", "other"];
+            DEFINE FIELD details            ON TABLE report TYPE string;
+            DEFINE FIELD status             ON TABLE report TYPE string                         ASSERT $value INSIDE ["open", "resolved"];
+            DEFINE FIELD assigned_to        ON TABLE report TYPE record(user);
+            DEFINE FIELD action             ON TABLE report TYPE string;
+            DEFINE FIELD note               ON TABLE report TYPE string;
+            DEFINE FIELD resolved_by        ON TABLE report TYPE record(user);
+            DEFINE FIELD created_at         ON TABLE report TYPE datetime                       ASSERT $value != NONE;
+            DEFINE FIELD resolved_at        ON TABLE report TYPE datetime;
+            DEFINE INDEX report_reporter    ON TABLE report COLUMNS reporter_id, target         UNIQUE;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(REPORT_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", REPORT_TBL_NAME);
+
+        Ok(())
+    }
+
+    // A user can report the same thing only once, so the number of open reports is the number
+    // of distinct users who reported it
+    pub async fn create_report(&self, info: &mut ReportForCreate) -> Result<Report, Error> {
+        self.check_report_target(&info.target).await?;
+        if self
+            .count_reports(&info.target, Some(&info.reporter_id))
+            .await?
+            > 0
+        {
+            return Err(Error::DBRecordAlreadyExist(
+                info.target.to_string(),
+                String::from("You already reported it"),
+            ));
+        }
+        info.created_at = chrono::offset::Utc::now();
+
+        let report: Report = self
+            .client
+            .create(REPORT_TBL_NAME)
+            .content(&info)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateRecord(err.to_string()))?;
+
+        self.hide_reported_content(
+            &report.target,
+            SiteConfig::parse_from_env_file()?.report_threshold,
+        )
+        .await?;
+
+        Ok(report)
+    }
+
+    // Users stay visible, only what they wrote can be hidden. Returns whether `target` was hidden
+    async fn hide_reported_content(&self, target: &Thing, threshold: usize) -> Result<bool, Error> {
+        if target.tb == USER_TBL_NAME || self.count_reports(target, None).await? < threshold {
+            return Ok(false);
+        }
+
+        log::info!("Hiding `{}` after reaching {} report(s)", target, threshold);
+        self.set_content_hidden(target, true).await?;
+
+        Ok(true)
+    }
+
+    pub async fn get_report(&self, id: &Thing) -> Result<Report, Error> {
+        let report: Option<Report> = self
+            .client
+            .select((id.tb.clone(), id.id.clone()))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(id.to_string(), err.to_string()))?;
+
+        report.ok_or(Error::DBRecordDidNotExist(id.to_string()))
+    }
+
+    // Oldest first so the reports are triaged in order
+    pub async fn list_reports(
+        &self,
+        query: &ReportQuery,
+        assignee: Option<&Thing>,
+    ) -> Result<(usize, Vec<Report>), Error> {
+        let mut filters = vec!["status = $status"];
+        if query.reason.is_some() {
+            filters.push("reason = $reason");
+        }
+        if query.kind.is_some() {
+            filters.push("meta::tb(target) = $kind");
+        }
+        if assignee.is_some() {
+            filters.push("assigned_to = $assignee");
+        }
+        let filter = filters.join(" AND ");
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE {} GROUP ALL; SELECT * FROM {} WHERE {} ORDER BY created_at ASC LIMIT {} START {};",
+            REPORT_TBL_NAME,
+            filter,
+            REPORT_TBL_NAME,
+            filter,
+            query
+                .limit
+                .unwrap_or(REPORT_PAGE_LENGTH)
+                .clamp(1, REPORT_MAX_PAGE_LENGTH),
+            query.offset.unwrap_or(0)
+        );
+
+        let mut response = self
+            .client
+            .query(sql)
+            .bind(("status", query.status.unwrap_or(ReportStatus::Open)))
+            .bind(("reason", query.reason))
+            .bind(("kind", &query.kind))
+            .bind(("assignee", assignee))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotSelectRecord(REPORT_TBL_NAME.to_string(), err.to_string())
+            })?;
+        let total: Option<ReportCount> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let reports: Vec<Report> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok((total.map(|total| total.total).unwrap_or(0), reports))
+    }
+
+    pub async fn assign_report(&self, report: &Thing, assignee: &Thing) -> Result<(), Error> {
+        let changes: Vec<OpChanges> = self
+            .client
+            .update((report.tb.clone(), report.id.clone()))
+            .patch(PatchOp::replace("/assigned_to", assignee))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(report.to_string(), err.to_string()))?;
+        log::debug!(
+            "Successfully assigned report: `{}` to `{}`. Changes: {:?}",
+            report,
+            assignee,
+            changes
+        );
+
+        Ok(())
+    }
+
+    // Applies the action to the reported content and closes every open report about it,
+    // returns the ids of the reports that were closed
+    pub async fn resolve_report(
+        &self,
+        context: &Context,
+        report: &Report,
+        resolution: &ReportResolution,
+    ) -> Result<Vec<Thing>, Error> {
+        let target = &report.target;
+        match (resolution.action, target.tb.as_str()) {
+            (ReportAction::Dismiss | ReportAction::Hide, USER_TBL_NAME) => {}
+            (ReportAction::Dismiss, _) => self.set_content_hidden(target, false).await?,
+            (ReportAction::Hide, _) => self.set_content_hidden(target, true).await?,
+            (ReportAction::Remove, COMMENT_TBL_NAME) => {
+                let comment = self.get_comment(target).await?;
+                self.delete_comment(&comment).await?;
+            }
+            (ReportAction::Remove, ARTICLE_TBL_NAME) => {
+                self.delete_article_with_id(target).await?;
+            }
+            (ReportAction::Remove, _) => self.delete_user_with_id(target).await?,
+        }
+
+        let sql = format!(
+            "UPDATE {} SET status = 'resolved', action = $action, note = $note, resolved_by = $resolved_by, resolved_at = time::now() WHERE target = $target AND status = 'open' RETURN id",
+            REPORT_TBL_NAME
+        );
+        let resolved: Vec<ReportId> = self
+            .client
+            .query(sql)
+            .bind(("action", resolution.action))
+            .bind(("note", &resolution.note))
+            .bind(("resolved_by", &context.user_id))
+            .bind(("target", target))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(target.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        log::debug!(
+            "Successfully resolved {} report(s) about `{}` with action: {:?}",
+            resolved.len(),
+            target,
+            resolution.action
+        );
+
+        Ok(resolved.into_iter().map(|report| report.id).collect())
+    }
+
+    // Open reports about `target`, only the ones from `reporter` when given
+    async fn count_reports(
+        &self,
+        target: &Thing,
+        reporter: Option<&Thing>,
+    ) -> Result<usize, Error> {
+        let filter = match reporter {
+            Some(_) => "target = $target AND reporter_id = $reporter_id",
+            None => "target = $target AND status = 'open'",
+        };
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE {} GROUP ALL",
+            REPORT_TBL_NAME, filter
+        );
+        let total: Option<ReportCount> = self
+            .client
+            .query(sql)
+            .bind(("target", target))
+            .bind(("reporter_id", reporter))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(target.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(total.map(|total| total.total).unwrap_or(0))
+    }
+
+    // Deleted comments and users can not be reported anymore
+    async fn check_report_target(&self, target: &Thing) -> Result<(), Error> {
+        let exists = match target.tb.as_str() {
+            COMMENT_TBL_NAME => !self.get_comment(target).await?.deleted,
+            ARTICLE_TBL_NAME => {
+                self.get_article_with_id(target).await?;
+                true
+            }
+            _ => !self.get_user_with_id(target).await?.deleted,
+        };
+        if !exists {
+            return Err(Error::DBRecordDidNotExist(target.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn set_content_hidden(&self, target: &Thing, hidden: bool) -> Result<(), Error> {
+        self.client
+            .query("UPDATE $target SET hidden = $hidden")
+            .bind(("target", target))
+            .bind(("hidden", hidden))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(target.to_string(), err.to_string()))?;
+        if target.tb == ARTICLE_TBL_NAME {
+            self.related.clear();
+            if let Some(tags) = self.get_article_with_id(target).await?.tags {
+                self.refresh_tag_counts(&tags).await?;
+            }
+        }
+        log::debug!("Successfully set `{}` as hidden: {}", target, hidden);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn report(database: &Database, reporter: &str, target: &str, status: &str) {
+        let sql = format!(
+            "CREATE report SET reporter_id = user:{}, target = {}, reason = 'spam', \
+             status = '{}', created_at = time::now()",
+            reporter, target, status
+        );
+        database.client.query(sql).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn hide_reported_content_waits_for_the_threshold() {
+        let database = Database::start_in_memory().await;
+        database
+            .client
+            .query(
+                "CREATE comment:one SET user_id = user:author, article_id = article:one, \
+                 status = 'approved', deleted = false, created_at = time::now()",
+            )
+            .await
+            .unwrap();
+        let target = Thing::from((COMMENT_TBL_NAME, "one"));
+        report(&database, "first", "comment:one", "open").await;
+        report(&database, "second", "comment:one", "open").await;
+        // Resolved reports were already looked at by a moderator
+        report(&database, "third", "comment:one", "resolved").await;
+
+        assert!(!database.hide_reported_content(&target, 3).await.unwrap());
+        assert!(!database.get_comment(&target).await.unwrap().hidden);
+
+        report(&database, "fourth", "comment:one", "open").await;
+        assert!(database.hide_reported_content(&target, 3).await.unwrap());
+        assert!(database.get_comment(&target).await.unwrap().hidden);
+    }
+
+    #[tokio::test]
+    async fn hide_reported_content_never_hides_users() {
+        let database = Database::start_in_memory().await;
+        report(&database, "first", "user:author", "open").await;
+
+        let target = Thing::from((USER_TBL_NAME, "author"));
+        assert!(!database.hide_reported_content(&target, 1).await.unwrap());
+    }
+}
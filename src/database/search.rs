@@ -0,0 +1,546 @@
+use crate::database::{
+    article::{ArticleSource, ARTICLE_TBL_NAME},
+    comment::COMMENT_TBL_NAME,
+    tag::TAG_TBL_NAME,
+    user::USER_TBL_NAME,
+    Database,
+};
+use crate::errors::Error;
+use crate::models::{
+    article::{Article, RelatedArticle},
+    comment::Comment,
+    search::{SearchDocument, SearchKind, SearchQuery, SearchResult, Suggestion, SuggestionKind},
+};
+use crate::s3;
+use crate::utils::{
+    html,
+    search::{self, Corpus, SNIPPET_LENGTH, TITLE_WEIGHT},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use surrealdb::sql::Thing;
+
+pub const SEARCH_TBL_NAME: &str = "search_document";
+const SEARCH_PAGE_LENGTH: usize = 20;
+const SEARCH_MAX_PAGE_LENGTH: usize = 100;
+// Fuzzy matches are ranked in process, so only this many candidates are fetched per type
+const SUGGESTION_CANDIDATES: usize = 200;
+// Share of the related article score coming from tags, the rest comes from the text
+const RELATED_TAG_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Deserialize)]
+struct SearchStatistics {
+    total: usize,
+    total_length: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchCount {
+    total: usize,
+}
+
+// What is needed of a matching document to show it, `score` is computed by the query
+#[derive(Debug, Deserialize)]
+struct SearchHit {
+    kind: SearchKind,
+    record: Thing,
+    article_id: Thing,
+    user_id: Thing,
+    title: Option<String>,
+    body: String,
+    score: f64,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SuggestionCandidate {
+    id: Thing,
+    label: String,
+    slug: Option<String>,
+}
+
+impl Database {
+    pub async fn create_search_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE TABLE search_document SCHEMAFULL;
+            DEFINE FIELD kind               ON TABLE search_document TYPE string          ASSERT $value INSIDE ["article", "comment"];
+            DEFINE FIELD record             ON TABLE search_document TYPE record(article, comment) ASSERT $value != NONE;
+            DEFINE FIELD article_id         ON TABLE search_document TYPE record(article) ASSERT $value != NONE;
+            DEFINE FIELD user_id            ON TABLE search_document TYPE record(user)    ASSERT $value != NONE;
+            DEFINE FIELD title              ON TABLE search_document TYPE string;
+            DEFINE FIELD body               ON TABLE search_document TYPE string;
+            DEFINE FIELD terms              ON TABLE search_document TYPE array;
+            DEFINE FIELD terms.*            ON TABLE search_document TYPE string;
+            DEFINE FIELD tokens             ON TABLE search_document TYPE array;
+            DEFINE FIELD tokens.*           ON TABLE search_document TYPE string;
+            DEFINE FIELD length             ON TABLE search_document TYPE int;
+            DEFINE FIELD created_at         ON TABLE search_document TYPE datetime        ASSERT $value != NONE;
+            DEFINE FIELD updated_at         ON TABLE search_document TYPE datetime;
+            DEFINE INDEX terms_index        ON TABLE search_document COLUMNS terms.*;
+            DEFINE INDEX article_id_index   ON TABLE search_document COLUMNS article_id;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(SEARCH_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", SEARCH_TBL_NAME);
+
+        Ok(())
+    }
+
+    // Without new content the body that is already indexed is kept
+    pub async fn index_article(
+        &self,
+        article: &Article,
+        content: Option<&str>,
+    ) -> Result<(), Error> {
+        let body = match content {
+            Some(content) => html::to_plain_text(content),
+            None => self
+                .get_search_document(&article.id)
+                .await?
+                .map(|document| document.body)
+                .unwrap_or_default(),
+        };
+
+        let document = SearchDocument {
+            kind: SearchKind::Article,
+            record: article.id.clone(),
+            article_id: article.id.clone(),
+            user_id: article.user_id.clone(),
+            title: Some(article.title.clone()),
+            body,
+            terms: Default::default(),
+            tokens: Default::default(),
+            length: Default::default(),
+            created_at: article.created_at,
+            updated_at: article.updated_at.unwrap_or(article.created_at),
+        };
+        self.save_search_document(document).await
+    }
+
+    pub async fn index_comment(&self, comment: &Comment) -> Result<(), Error> {
+        let document = SearchDocument {
+            kind: SearchKind::Comment,
+            record: comment.id.clone(),
+            article_id: comment.article_id.clone(),
+            user_id: comment.user_id.clone(),
+            title: None,
+            body: html::strip_markdown(comment.content.as_deref().unwrap_or_default()),
+            terms: Default::default(),
+            tokens: Default::default(),
+            length: Default::default(),
+            created_at: comment.created_at,
+            updated_at: comment.updated_at.unwrap_or(comment.created_at),
+        };
+        self.save_search_document(document).await
+    }
+
+    // Drops the article and all of its comments from the index
+    pub async fn remove_article_from_search(&self, article_id: &Thing) -> Result<(), Error> {
+        let sql = format!("DELETE {} WHERE article_id = $article_id", SEARCH_TBL_NAME);
+        self.client
+            .query(sql)
+            .bind(("article_id", article_id))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotDeleteRecord(article_id.to_string(), err.to_string())
+            })?;
+
+        Ok(())
+    }
+
+    async fn get_search_document(&self, record: &Thing) -> Result<Option<SearchDocument>, Error> {
+        let document: Option<SearchDocument> = self
+            .client
+            .select((SEARCH_TBL_NAME, search_document_key(record)))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(record.to_string(), err.to_string()))?;
+
+        Ok(document)
+    }
+
+    async fn save_search_document(&self, mut document: SearchDocument) -> Result<(), Error> {
+        let title = tokenize_title(document.title.as_deref());
+        let mut tokens = search::tokenize(&document.body);
+        for _ in 0..TITLE_WEIGHT {
+            tokens.extend(title.iter().cloned());
+        }
+        document.terms = search::unique_terms(&tokens);
+        document.length = tokens.len();
+        document.tokens = tokens;
+
+        self.client
+            .query("UPDATE type::thing($table, $key) CONTENT $document")
+            .bind(("table", SEARCH_TBL_NAME))
+            .bind(("key", search_document_key(&document.record)))
+            .bind(("document", &document))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotUpdateRecord(document.record.to_string(), err.to_string())
+            })?;
+        log::debug!("Successfully indexed record: `{}`", document.record);
+
+        Ok(())
+    }
+
+    // Only public, listed articles and approved comments that were not deleted or hidden after
+    // reports can be found
+    pub async fn search(
+        &self,
+        query: &SearchQuery,
+        user_id: Option<&Thing>,
+    ) -> Result<(usize, Vec<SearchResult>), Error> {
+        let terms = search::unique_terms(&search::tokenize(&query.q));
+        if terms.is_empty() {
+            return Ok((0, Vec::new()));
+        }
+
+        let mut filters = vec![
+            "terms CONTAINSANY $terms",
+            "article_id.public = true",
+            "article_id.unlisted != true",
+            "article_id.hidden != true",
+            "(kind = 'article' OR (record.deleted = false AND record.status = 'approved' AND record.hidden != true))",
+        ];
+        if query.kind.is_some() {
+            filters.push("kind = $kind");
+        }
+        if user_id.is_some() {
+            filters.push("user_id = $user_id");
+        }
+        if query.tag.is_some() {
+            filters.push("article_id.tags CONTAINS $tag");
+        }
+        let filter = filters.join(" AND ");
+        let limit = query
+            .limit
+            .unwrap_or(SEARCH_PAGE_LENGTH)
+            .clamp(1, SEARCH_MAX_PAGE_LENGTH);
+        let start = (query.page.unwrap_or(1).max(1) - 1).saturating_mul(limit);
+        let sql = format!(
+            "SELECT count() AS total FROM {} WHERE {} GROUP ALL; SELECT kind, record, article_id, user_id, title, body, created_at, {} AS score FROM {} WHERE {} ORDER BY score DESC, created_at DESC LIMIT {} START {};",
+            SEARCH_TBL_NAME,
+            filter,
+            score_expression(terms.len()),
+            SEARCH_TBL_NAME,
+            filter,
+            limit,
+            start
+        );
+
+        let corpus = self.get_search_corpus(&terms).await?;
+        let mut request = self
+            .client
+            .query(sql)
+            .bind(("terms", &terms))
+            .bind(("kind", query.kind))
+            .bind(("user_id", user_id))
+            .bind(("tag", &query.tag))
+            .bind(("k1", search::K1))
+            .bind(("b", search::B))
+            .bind(("average_length", corpus.average_length.max(1.0)));
+        for (index, term) in terms.iter().enumerate() {
+            request = request
+                .bind((format!("term{}", index), term))
+                .bind((format!("idf{}", index), corpus.idf(term)));
+        }
+        let mut response = request
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?;
+        let total: Option<SearchCount> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let hits: Vec<SearchHit> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        let results = hits
+            .into_iter()
+            .map(|hit| {
+                let snippet = if hit.body.is_empty() {
+                    search::highlight(
+                        hit.title.as_deref().unwrap_or_default(),
+                        &terms,
+                        SNIPPET_LENGTH,
+                    )
+                } else {
+                    search::highlight(&hit.body, &terms, SNIPPET_LENGTH)
+                };
+
+                SearchResult {
+                    kind: hit.kind,
+                    id: hit.record,
+                    article_id: hit.article_id,
+                    user_id: hit.user_id,
+                    title: hit.title,
+                    snippet,
+                    score: hit.score,
+                    created_at: hit.created_at,
+                }
+            })
+            .collect();
+
+        Ok((total.map(|total| total.total).unwrap_or(0), results))
+    }
+
+    // Document frequencies of all the terms are counted in a single pass over the documents
+    // that hold at least one of them
+    async fn get_search_corpus(&self, terms: &[String]) -> Result<Corpus, Error> {
+        let counts: Vec<String> = (0..terms.len())
+            .map(|index| format!("count(terms CONTAINS $term{}) AS term{}", index, index))
+            .collect();
+        let sql = format!(
+            "SELECT count() AS total, math::sum(length) AS total_length FROM {} GROUP ALL; SELECT {} FROM {} WHERE terms CONTAINSANY $terms GROUP ALL;",
+            SEARCH_TBL_NAME,
+            counts.join(", "),
+            SEARCH_TBL_NAME
+        );
+
+        let mut query = self.client.query(sql).bind(("terms", terms));
+        for (index, term) in terms.iter().enumerate() {
+            query = query.bind((format!("term{}", index), term));
+        }
+        let mut response = query
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?;
+
+        let statistics: Option<SearchStatistics> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let counts: Option<HashMap<String, usize>> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let counts = counts.unwrap_or_default();
+        let frequencies = terms
+            .iter()
+            .enumerate()
+            .map(|(index, term)| {
+                let count = counts.get(&format!("term{}", index)).copied();
+                (term.clone(), count.unwrap_or(0))
+            })
+            .collect();
+
+        let (total, total_length) = statistics
+            .map(|statistics| (statistics.total, statistics.total_length))
+            .unwrap_or((0, 0));
+        Ok(Corpus {
+            total,
+            average_length: total_length as f64 / total.max(1) as f64,
+            frequencies,
+        })
+    }
+
+    // Prefix and fuzzy matches on usernames, tag names and titles, `limit` per type
+    pub async fn suggest(
+        &self,
+        query: &str,
+        kinds: &[SuggestionKind],
+        limit: usize,
+    ) -> Result<Vec<Suggestion>, Error> {
+        let query = query.trim().to_lowercase();
+        let key = format!("{:?}|{}|{}", kinds, limit, query);
+        if let Some(suggestions) = self.suggestions.get(&key) {
+            return Ok(suggestions);
+        }
+
+        let mut suggestions = Vec::new();
+        for kind in kinds {
+            let sql = match kind {
+                SuggestionKind::User => format!(
+                    "SELECT id, username AS label FROM {} WHERE deleted = false AND string::lowercase(username) ~ $query LIMIT {}",
+                    USER_TBL_NAME, SUGGESTION_CANDIDATES
+                ),
+                SuggestionKind::Tag => format!(
+                    "SELECT id, name AS label, slug FROM {} WHERE count > 0 AND string::lowercase(name) ~ $query LIMIT {}",
+                    TAG_TBL_NAME, SUGGESTION_CANDIDATES
+                ),
+                SuggestionKind::Article => format!(
+                    "SELECT id, title AS label, slug FROM {} WHERE public = true AND unlisted != true AND hidden != true AND user_id.deleted = false AND string::lowercase(title) ~ $query LIMIT {}",
+                    ARTICLE_TBL_NAME, SUGGESTION_CANDIDATES
+                ),
+            };
+            let mut candidates: Vec<SuggestionCandidate> = self
+                .client
+                .query(sql)
+                .bind(("query", &query))
+                .await
+                .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?
+                .take(0)
+                .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+            candidates.sort_by_key(|candidate| {
+                (
+                    search::match_rank(&candidate.label, &query),
+                    candidate.label.len(),
+                )
+            });
+            suggestions.extend(
+                candidates
+                    .into_iter()
+                    .take(limit)
+                    .map(|candidate| Suggestion {
+                        kind: *kind,
+                        id: candidate.id,
+                        label: candidate.label,
+                        slug: candidate.slug,
+                    }),
+            );
+        }
+
+        self.suggestions.insert(key, suggestions.clone());
+        Ok(suggestions)
+    }
+
+    // Mixes tag overlap with the TF-IDF similarity of the indexed text of public articles
+    pub async fn list_related_articles(
+        &self,
+        article: &Article,
+        limit: usize,
+    ) -> Result<Vec<RelatedArticle>, Error> {
+        let key = format!("{}|{}", article.id, limit);
+        if let Some(related) = self.related.get(&key) {
+            return Ok(related);
+        }
+
+        let articles = self
+            .list_public_articles(&ArticleSource::Site, None)
+            .await?;
+        let sql = format!(
+            "SELECT * FROM {} WHERE kind = 'article' AND ((article_id.public = true AND article_id.unlisted != true AND article_id.hidden != true) OR article_id = $article_id)",
+            SEARCH_TBL_NAME
+        );
+        let documents: Vec<SearchDocument> = self
+            .client
+            .query(sql)
+            .bind(("article_id", &article.id))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        let frequencies: HashMap<String, HashMap<String, f64>> = documents
+            .iter()
+            .map(|document| {
+                let tokens = [
+                    tokenize_title(document.title.as_deref()),
+                    search::tokenize(&document.body),
+                ]
+                .concat();
+                (
+                    document.article_id.to_string(),
+                    search::term_frequencies(&tokens),
+                )
+            })
+            .collect();
+        let idf = search::inverse_document_frequencies(
+            &frequencies.values().cloned().collect::<Vec<_>>(),
+        );
+        let vector = frequencies
+            .get(&article.id.to_string())
+            .map(|frequencies| search::tf_idf(frequencies, &idf))
+            .unwrap_or_default();
+        let tags = article.tags.clone().unwrap_or_default();
+
+        let mut related: Vec<RelatedArticle> = articles
+            .into_iter()
+            .filter(|candidate| candidate.id != article.id)
+            .filter_map(|candidate| {
+                let candidate_tags = candidate.tags.unwrap_or_default();
+                let text = frequencies
+                    .get(&candidate.id.to_string())
+                    .map(|frequencies| {
+                        search::cosine_similarity(&vector, &search::tf_idf(frequencies, &idf))
+                    })
+                    .unwrap_or(0.0);
+                let score = RELATED_TAG_WEIGHT * search::jaccard_similarity(&tags, &candidate_tags)
+                    + (1.0 - RELATED_TAG_WEIGHT) * text;
+                if score <= 0.0 {
+                    return None;
+                }
+
+                Some(RelatedArticle {
+                    id: candidate.id,
+                    user_id: candidate.user_id,
+                    title: candidate.title,
+                    slug: candidate.slug,
+                    tags: candidate_tags,
+                    score,
+                    created_at: candidate.created_at,
+                })
+            })
+            .collect();
+        related.sort_by(|first, second| {
+            second
+                .score
+                .total_cmp(&first.score)
+                .then(second.created_at.cmp(&first.created_at))
+        });
+        related.truncate(limit);
+
+        self.related.insert(key, related.clone());
+        Ok(related)
+    }
+
+    // Rebuilds the whole index, used for content that existed before search was added
+    pub async fn reindex_search(&self) -> Result<usize, Error> {
+        let sql = format!(
+            "DELETE {}; SELECT * FROM {}; SELECT * FROM {} WHERE deleted = false;",
+            SEARCH_TBL_NAME, ARTICLE_TBL_NAME, COMMENT_TBL_NAME
+        );
+        let mut response = self
+            .client
+            .query(sql)
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?;
+        let articles: Vec<Article> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let comments: Vec<Comment> = response
+            .take(2)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        for article in &articles {
+            let content = if article.article_uri.is_empty() {
+                None
+            } else {
+                Some(s3::get_object(&article.article_uri).await?)
+            };
+            self.index_article(article, content.as_deref()).await?;
+        }
+        for comment in &comments {
+            self.index_comment(comment).await?;
+        }
+
+        Ok(articles.len() + comments.len())
+    }
+}
+
+fn search_document_key(record: &Thing) -> String {
+    format!("{}_{}", record.tb, record.id)
+}
+
+// BM25 of the document for each term of the query, `tf` is how often the term appears in its
+// tokens. Every operation is parenthesized as SurrealQL operators have no precedence
+fn score_expression(terms: usize) -> String {
+    let scores: Vec<String> = (0..terms)
+        .map(|index| {
+            let frequency = format!(
+                "(array::len(tokens) - array::len(array::complement(tokens, [$term{}])))",
+                index
+            );
+            format!(
+                "((($idf{} * {}) * ($k1 + 1)) / ({} + ($k1 * ((1 - $b) + (($b * length) / $average_length)))))",
+                index, frequency, frequency
+            )
+        })
+        .collect();
+
+    format!("({})", scores.join(" + "))
+}
+
+fn tokenize_title(title: Option<&str>) -> Vec<String> {
+    title.map(search::tokenize).unwrap_or_default()
+}
@@ -0,0 +1,361 @@
+use crate::database::{article::ARTICLE_TBL_NAME, Database};
+use crate::errors::Error;
+use crate::models::series::{Series, SeriesEntry, SeriesForCreate, SeriesNavigation};
+use crate::server::context::Context;
+use crate::utils::OpChanges;
+
+use surrealdb::{opt::PatchOp, sql::Thing};
+
+pub const SERIES_TBL_NAME: &str = "series";
+
+impl Database {
+    pub async fn create_series_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE TABLE series SCHEMAFULL;
+            DEFINE FIELD user_id            ON TABLE series TYPE record(user)    ASSERT $value != NONE;
+            DEFINE FIELD title              ON TABLE series TYPE string          ASSERT $value != NONE;
+            DEFINE FIELD description        ON TABLE series TYPE string;
+            DEFINE FIELD articles           ON TABLE series TYPE array;
+            DEFINE FIELD articles.*         ON TABLE series TYPE record(article) ASSERT $value != NONE;
+            DEFINE FIELD created_at         ON TABLE series TYPE datetime        ASSERT $value != NONE;
+            DEFINE FIELD updated_at         ON TABLE series TYPE datetime;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(SERIES_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", SERIES_TBL_NAME);
+
+        Ok(())
+    }
+
+    pub async fn create_series(&self, info: &mut SeriesForCreate) -> Result<Thing, Error> {
+        info.created_at = chrono::offset::Utc::now();
+
+        let series: Series = self
+            .client
+            .create(SERIES_TBL_NAME)
+            .content(info)
+            .await
+            .map_err(|err| Error::DBCouldNotCreateRecord(err.to_string()))?;
+
+        Ok(series.id)
+    }
+
+    pub async fn list_series_for_user(&self, user_id: &Thing) -> Result<Vec<Series>, Error> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE user_id = $user_id ORDER BY created_at DESC",
+            SERIES_TBL_NAME
+        );
+        let series: Vec<Series> = self
+            .client
+            .query(sql)
+            .bind(("user_id", user_id))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(user_id.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(series)
+    }
+
+    pub async fn get_series(&self, id: &Thing) -> Result<Series, Error> {
+        let series: Series = self
+            .client
+            .select((id.tb.clone(), id.id.clone()))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(id.to_string(), err.to_string()))?;
+
+        Ok(series)
+    }
+
+    // Readers only see the articles they could open themselves, the author and admins see all
+    pub async fn get_series_for_reader(
+        &self,
+        id: &Thing,
+        context: &Option<Context>,
+    ) -> Result<Series, Error> {
+        let mut series = self.get_series(id).await?;
+        if !is_owner(&series, context) {
+            let articles = series.articles.take().unwrap_or_default();
+            series.articles = Some(self.filter_visible_articles(articles, true).await?);
+        }
+
+        Ok(series)
+    }
+
+    pub async fn delete_series(&self, id: &Thing) -> Result<Series, Error> {
+        let series: Series = self
+            .client
+            .delete((id.tb.clone(), id.id.clone()))
+            .await
+            .map_err(|err| Error::DBCouldNotDeleteRecord(id.to_string(), err.to_string()))?;
+        log::debug!("Successfully delete series with id: {}", &id);
+
+        Ok(series)
+    }
+
+    pub async fn add_article_to_series(
+        &self,
+        series: &Series,
+        article_id: &Thing,
+    ) -> Result<(), Error> {
+        let mut articles = series.articles.clone().unwrap_or_default();
+        if articles.contains(article_id) {
+            return Err(Error::DBRecordAlreadyExist(
+                article_id.to_string(),
+                format!("Article is already part of series `{}`", series.id),
+            ));
+        }
+        articles.push(article_id.clone());
+
+        self.update_series_articles(&series.id, articles).await
+    }
+
+    pub async fn remove_article_from_series(
+        &self,
+        series: &Series,
+        article_id: &Thing,
+    ) -> Result<(), Error> {
+        let mut articles = series.articles.clone().unwrap_or_default();
+        if !articles.contains(article_id) {
+            return Err(Error::DBRecordDidNotExist(article_id.to_string()));
+        }
+        articles.retain(|element| element != article_id);
+
+        self.update_series_articles(&series.id, articles).await
+    }
+
+    // The new order must contain exactly the articles that are already in the series
+    pub async fn reorder_series(&self, series: &Series, order: Vec<Thing>) -> Result<(), Error> {
+        let mut current = series.articles.clone().unwrap_or_default();
+        let mut requested = order.clone();
+        current.sort_by_key(|element| element.to_string());
+        requested.sort_by_key(|element| element.to_string());
+        if current != requested {
+            return Err(Error::ServerBadRequest(String::from(
+                "New order must list every article of the series exactly once",
+            )));
+        }
+
+        self.update_series_articles(&series.id, order).await
+    }
+
+    async fn update_series_articles(&self, id: &Thing, articles: Vec<Thing>) -> Result<(), Error> {
+        let changes: Vec<OpChanges> = self
+            .client
+            .update((id.tb.clone(), id.id.clone()))
+            .patch(PatchOp::replace("/articles", articles))
+            .patch(PatchOp::replace("/updated_at", chrono::offset::Utc::now()))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(id.to_string(), err.to_string()))?;
+        log::debug!(
+            "Successfully updated series with id: `{}`, changes: {:?}",
+            &id,
+            changes
+        );
+
+        Ok(())
+    }
+
+    pub async fn remove_article_from_all_series(&self, article_id: &Thing) -> Result<(), Error> {
+        let sql = format!(
+            "UPDATE {} SET articles -= $article_id, updated_at = time::now() \
+             WHERE articles CONTAINS $article_id",
+            SERIES_TBL_NAME
+        );
+        self.client
+            .query(sql)
+            .bind(("article_id", article_id))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotUpdateRecord(article_id.to_string(), err.to_string())
+            })?;
+
+        Ok(())
+    }
+
+    // Drafts and hidden articles are skipped unless the caller is the author or an admin, parts
+    // are numbered among the articles that are left
+    pub async fn get_series_navigation_for_article(
+        &self,
+        article_id: &Thing,
+        context: &Option<Context>,
+    ) -> Result<Vec<SeriesNavigation>, Error> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE articles CONTAINS $article_id",
+            SERIES_TBL_NAME
+        );
+        let series: Vec<Series> = self
+            .client
+            .query(sql)
+            .bind(("article_id", article_id))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(article_id.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        let mut navigation = Vec::new();
+        for series in series {
+            let articles = series.articles.clone().unwrap_or_default();
+            let articles = if is_owner(&series, context) {
+                articles
+            } else {
+                self.filter_visible_articles(articles, false).await?
+            };
+            let Some(position) = articles.iter().position(|element| element == article_id) else {
+                continue;
+            };
+            let previous = match position.checked_sub(1) {
+                Some(index) => self.get_series_entry(&articles[index]).await?,
+                None => None,
+            };
+            let next = match articles.get(position + 1) {
+                Some(id) => self.get_series_entry(id).await?,
+                None => None,
+            };
+
+            navigation.push(SeriesNavigation {
+                id: series.id,
+                title: series.title,
+                part: position + 1,
+                total: articles.len(),
+                previous,
+                next,
+            });
+        }
+
+        Ok(navigation)
+    }
+
+    // Keeps the order of `articles`. Unlisted articles are dropped too when `listed_only` is set
+    async fn filter_visible_articles(
+        &self,
+        articles: Vec<Thing>,
+        listed_only: bool,
+    ) -> Result<Vec<Thing>, Error> {
+        let sql = format!(
+            "SELECT VALUE id FROM {} WHERE id INSIDE $articles AND public = true AND hidden != true \
+             AND ($listed_only = false OR unlisted != true)",
+            ARTICLE_TBL_NAME
+        );
+        let visible: Vec<Thing> = self
+            .client
+            .query(sql)
+            .bind(("articles", &articles))
+            .bind(("listed_only", listed_only))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(articles
+            .into_iter()
+            .filter(|article| visible.contains(article))
+            .collect())
+    }
+
+    async fn get_series_entry(&self, article_id: &Thing) -> Result<Option<SeriesEntry>, Error> {
+        let entry: Option<SeriesEntry> = self
+            .client
+            .query("SELECT id, title, slug FROM $article_id")
+            .bind(("article_id", article_id))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(article_id.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(entry)
+    }
+}
+
+fn is_owner(series: &Series, context: &Option<Context>) -> bool {
+    context.as_ref().is_some_and(|context| {
+        context
+            .check_permissions(Some(series.user_id.clone()), false)
+            .is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::Role;
+
+    async fn database_with_series() -> Database {
+        let database = Database::start_in_memory().await;
+        database
+            .client
+            .query(
+                "CREATE article:listed SET user_id = user:author, title = 'Listed', slug = 'listed', \
+                 public = true, created_at = time::now();
+                 CREATE article:unlisted SET user_id = user:author, title = 'Unlisted', \
+                 slug = 'unlisted', public = true, unlisted = true, created_at = time::now();
+                 CREATE article:draft SET user_id = user:author, title = 'Draft', slug = 'draft', \
+                 public = false, created_at = time::now();
+                 CREATE article:hidden SET user_id = user:author, title = 'Hidden', slug = 'hidden', \
+                 public = true, hidden = true, created_at = time::now();
+                 CREATE series:one SET user_id = user:author, title = 'Series', created_at = time::now(), \
+                 articles = [article:draft, article:listed, article:hidden, article:unlisted];",
+            )
+            .await
+            .unwrap();
+
+        database
+    }
+
+    fn context(user_id: &str) -> Option<Context> {
+        Some(Context {
+            user_id: Thing::from(("user", user_id)),
+            user_role: Role::User,
+        })
+    }
+
+    #[tokio::test]
+    async fn get_series_for_reader_shows_only_listed_articles_to_readers() {
+        let database = database_with_series().await;
+        let id = Thing::from((SERIES_TBL_NAME, "one"));
+
+        let series = database.get_series_for_reader(&id, &None).await.unwrap();
+        assert_eq!(
+            series.articles,
+            Some(vec![Thing::from((ARTICLE_TBL_NAME, "listed"))])
+        );
+
+        let series = database
+            .get_series_for_reader(&id, &context("reader"))
+            .await
+            .unwrap();
+        assert_eq!(series.articles.map(|articles| articles.len()), Some(1));
+
+        let series = database
+            .get_series_for_reader(&id, &context("author"))
+            .await
+            .unwrap();
+        assert_eq!(series.articles.map(|articles| articles.len()), Some(4));
+    }
+
+    #[tokio::test]
+    async fn series_navigation_skips_drafts_and_hidden_articles_for_readers() {
+        let database = database_with_series().await;
+        let article_id = Thing::from((ARTICLE_TBL_NAME, "listed"));
+
+        let navigation = database
+            .get_series_navigation_for_article(&article_id, &None)
+            .await
+            .unwrap();
+        assert_eq!(navigation.len(), 1);
+        assert_eq!((navigation[0].part, navigation[0].total), (1, 2));
+        assert!(navigation[0].previous.is_none());
+        assert_eq!(
+            navigation[0].next.as_ref().map(|entry| entry.slug.as_str()),
+            Some("unlisted")
+        );
+
+        let navigation = database
+            .get_series_navigation_for_article(&article_id, &context("author"))
+            .await
+            .unwrap();
+        assert_eq!((navigation[0].part, navigation[0].total), (2, 4));
+    }
+}
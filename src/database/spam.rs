@@ -0,0 +1,144 @@
+use crate::database::Database;
+use crate::errors::Error;
+use crate::models::{
+    comment::Comment,
+    spam::{SpamCounts, SpamLabel, SpamToken},
+};
+use crate::utils::spam;
+
+use std::collections::HashMap;
+use surrealdb::sql::Thing;
+
+pub const SPAM_TOKEN_TBL_NAME: &str = "spam_token";
+pub const SPAM_TOTAL_TBL_NAME: &str = "spam_total";
+// The only record of `spam_total`, holding the number of documents the filter learnt from
+const SPAM_TOTAL_ID: &str = "documents";
+
+impl Database {
+    pub async fn create_spam_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE TABLE spam_token SCHEMAFULL;
+            DEFINE FIELD spam       ON TABLE spam_token TYPE int ASSERT $value != NONE;
+            DEFINE FIELD ham        ON TABLE spam_token TYPE int ASSERT $value != NONE;
+
+            DEFINE TABLE spam_total SCHEMAFULL;
+            DEFINE FIELD spam       ON TABLE spam_total TYPE int ASSERT $value != NONE;
+            DEFINE FIELD ham        ON TABLE spam_total TYPE int ASSERT $value != NONE;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(SPAM_TOKEN_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!(
+            "Successfully create table: `{}` and `{}`",
+            SPAM_TOKEN_TBL_NAME,
+            SPAM_TOTAL_TBL_NAME
+        );
+
+        Ok(())
+    }
+
+    // The highest of the heuristics and the trained model
+    pub async fn get_spam_score(&self, content: &str, blocklist: &[String]) -> Result<f64, Error> {
+        let heuristic = spam::heuristic_score(content, blocklist);
+        if heuristic >= 1.0 {
+            return Ok(heuristic);
+        }
+
+        let tokens = spam::tokens(content);
+        let records: Vec<Thing> = tokens
+            .iter()
+            .map(|token| Thing::from((SPAM_TOKEN_TBL_NAME, token.as_str())))
+            .collect();
+        let mut response = self
+            .client
+            .query("SELECT * FROM type::thing($table, $id); SELECT * FROM $records;")
+            .bind(("table", SPAM_TOTAL_TBL_NAME))
+            .bind(("id", SPAM_TOTAL_ID))
+            .bind(("records", records))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotSelectRecord(SPAM_TOKEN_TBL_NAME.to_string(), err.to_string())
+            })?;
+        let documents: Option<SpamCounts> = response
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let counts: Vec<SpamToken> = response
+            .take(1)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+        let counts: HashMap<String, SpamCounts> = counts
+            .into_iter()
+            .map(|token| (token.id.id.to_raw(), token.counts))
+            .collect();
+
+        let bayes = spam::bayes_score(&documents.unwrap_or_default(), &counts, &tokens);
+
+        Ok(bayes.unwrap_or(0.0).max(heuristic))
+    }
+
+    // Moves the comment to its new class, undoing what an earlier decision taught the filter.
+    // Edits drop the label, so it always belongs to the current content
+    pub async fn train_spam_filter(
+        &self,
+        comment: &Comment,
+        label: Option<SpamLabel>,
+    ) -> Result<(), Error> {
+        if comment.spam_label == label {
+            return Ok(());
+        }
+
+        let tokens = spam::tokens(comment.content.as_deref().unwrap_or_default());
+        if let Some(old_label) = comment.spam_label {
+            self.update_spam_counts(&tokens, old_label, -1).await?;
+        }
+        if let Some(label) = label {
+            self.update_spam_counts(&tokens, label, 1).await?;
+        }
+
+        self.client
+            .query("UPDATE $comment SET spam_label = $label")
+            .bind(("comment", &comment.id))
+            .bind(("label", label))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotUpdateRecord(comment.id.to_string(), err.to_string())
+            })?;
+        log::debug!(
+            "Successfully trained spam filter with comment: `{}`",
+            comment.id
+        );
+
+        Ok(())
+    }
+
+    async fn update_spam_counts(
+        &self,
+        tokens: &[String],
+        label: SpamLabel,
+        delta: i64,
+    ) -> Result<(), Error> {
+        let (spam, ham) = match label {
+            SpamLabel::Spam => (delta, 0),
+            SpamLabel::Ham => (0, delta),
+        };
+        let records: Vec<Thing> = tokens
+            .iter()
+            .map(|token| Thing::from((SPAM_TOKEN_TBL_NAME, token.as_str())))
+            .collect();
+
+        // Updating a record id creates it when it does not exist yet
+        self.client
+            .query("UPDATE $records SET spam += $spam, ham += $ham; UPDATE type::thing($table, $id) SET spam += $spam, ham += $ham;")
+            .bind(("records", records))
+            .bind(("table", SPAM_TOTAL_TBL_NAME))
+            .bind(("id", SPAM_TOTAL_ID))
+            .bind(("spam", spam))
+            .bind(("ham", ham))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotUpdateRecord(SPAM_TOKEN_TBL_NAME.to_string(), err.to_string())
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use crate::database::{article::ARTICLE_TBL_NAME, Database};
+use crate::errors::Error;
+use crate::models::{
+    article::Article,
+    tag::{Tag, TagForCreate},
+};
+
+use surrealdb::sql::Thing;
+
+pub const TAG_TBL_NAME: &str = "tag";
+
+impl Database {
+    pub async fn create_tag_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE TABLE tag SCHEMAFULL;
+            DEFINE FIELD name               ON TABLE tag TYPE string          ASSERT $value != NONE;
+            DEFINE FIELD slug               ON TABLE tag TYPE string          ASSERT $value != NONE;
+            DEFINE FIELD count              ON TABLE tag TYPE int             ASSERT $value != NONE;
+            DEFINE FIELD created_at         ON TABLE tag TYPE datetime        ASSERT $value != NONE;
+            DEFINE FIELD updated_at         ON TABLE tag TYPE datetime;
+            DEFINE INDEX tag_slug_index     ON TABLE tag COLUMNS slug         UNIQUE;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(TAG_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", TAG_TBL_NAME);
+
+        Ok(())
+    }
+
+    // Creates the tags that do not exist yet and recounts how many listed articles use them, so
+    // the tag cloud does not give drafts away
+    pub async fn sync_tags(&self, tags: &[TagForCreate]) -> Result<(), Error> {
+        let sql = r#"
+            UPDATE type::thing($table, $slug) SET
+                name       = $name,
+                slug       = $slug,
+                count      = count((SELECT id FROM article WHERE public = true AND unlisted != true AND hidden != true AND tags CONTAINS $slug)),
+                created_at = created_at OR time::now(),
+                updated_at = time::now();
+        "#;
+
+        for tag in tags {
+            self.client
+                .query(sql)
+                .bind(("table", TAG_TBL_NAME))
+                .bind(tag)
+                .await
+                .map_err(|err| Error::DBCouldNotUpdateRecord(tag.slug.clone(), err.to_string()))?;
+            log::debug!("Successfully synced tag: `{}`", tag.slug);
+        }
+
+        Ok(())
+    }
+
+    pub async fn refresh_tag_counts(&self, slugs: &[String]) -> Result<(), Error> {
+        let sql = r#"
+            UPDATE tag SET
+                count = count((SELECT id FROM article WHERE public = true AND unlisted != true AND hidden != true AND tags CONTAINS $parent.slug))
+            WHERE slug INSIDE $slugs;
+        "#;
+
+        self.client
+            .query(sql)
+            .bind(("slugs", slugs))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(slugs.join(", "), err.to_string()))?;
+        log::debug!("Successfully refreshed count for tags: {:?}", slugs);
+
+        Ok(())
+    }
+
+    pub async fn list_tags(&self) -> Result<Vec<Tag>, Error> {
+        let sql = "SELECT * FROM tag WHERE count > 0 ORDER BY count DESC, name ASC";
+        let tags: Vec<Tag> = self
+            .client
+            .query(sql)
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(tags)
+    }
+
+    pub async fn get_tag(&self, slug: &str) -> Result<Tag, Error> {
+        let tag: Option<Tag> = self
+            .client
+            .select((TAG_TBL_NAME, slug))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(slug.to_string(), err.to_string()))?;
+
+        tag.ok_or(Error::DBRecordDidNotExist(
+            Thing::from((TAG_TBL_NAME, slug)).to_string(),
+        ))
+    }
+
+    pub async fn list_articles_for_tag(&self, slug: &str) -> Result<Vec<Article>, Error> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE public = true AND unlisted != true AND hidden != true AND tags CONTAINS $slug ORDER BY created_at DESC",
+            ARTICLE_TBL_NAME
+        );
+        let articles: Vec<Article> = self
+            .client
+            .query(sql)
+            .bind(("slug", slug))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(slug.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(articles)
+    }
+
+    // Renaming a tag to a slug that is already taken merges both tags
+    pub async fn rename_tag(&self, tag: &Tag, name: &str) -> Result<Tag, Error> {
+        let new_tag = TagForCreate::from_name(name).ok_or(Error::ServerEmptyFormFromUser)?;
+        if new_tag.slug == tag.slug {
+            self.sync_tags(std::slice::from_ref(&new_tag)).await?;
+        } else if let Ok(existing) = self.get_tag(&new_tag.slug).await {
+            return self.merge_tag(tag, &existing).await;
+        } else {
+            self.move_tag(tag, &new_tag).await?;
+        }
+
+        self.get_tag(&new_tag.slug).await
+    }
+
+    pub async fn merge_tag(&self, tag: &Tag, into: &Tag) -> Result<Tag, Error> {
+        if tag.slug == into.slug {
+            return Err(Error::DBRecordAlreadyExist(
+                into.id.to_string(),
+                "Could not merge a tag into itself".to_string(),
+            ));
+        }
+
+        self.move_tag(
+            tag,
+            &TagForCreate {
+                name: into.name.clone(),
+                slug: into.slug.clone(),
+            },
+        )
+        .await?;
+
+        self.get_tag(&into.slug).await
+    }
+
+    async fn move_tag(&self, tag: &Tag, into: &TagForCreate) -> Result<(), Error> {
+        let sql = r#"
+            UPDATE article SET tags = array::union(tags, [$into]) WHERE tags CONTAINS $from;
+            UPDATE article SET tags -= $from WHERE tags CONTAINS $from;
+            DELETE type::thing($table, $from);
+        "#;
+
+        self.client
+            .query(sql)
+            .bind(("table", TAG_TBL_NAME))
+            .bind(("from", &tag.slug))
+            .bind(("into", &into.slug))
+            .await
+            .map_err(|err| Error::DBCouldNotUpdateRecord(tag.id.to_string(), err.to_string()))?;
+        self.sync_tags(std::slice::from_ref(into)).await?;
+        log::debug!(
+            "Successfully moved articles from tag `{}` to tag `{}`",
+            tag.slug,
+            into.slug
+        );
+
+        Ok(())
+    }
+}
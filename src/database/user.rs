@@ -7,6 +7,8 @@ use crate::{utils, utils::OpChanges};
 use surrealdb::{opt::PatchOp, sql::Thing};
 
 pub const USER_TBL_NAME: &str = "user";
+// `in` blocked `out`
+pub const BLOCK_TBL_NAME: &str = "blocks";
 const USER_PROFILE_FOLDER: &str = "user_profile_pictures";
 
 impl Database {
@@ -18,6 +20,7 @@ impl Database {
             DEFINE FIELD last_name          ON TABLE user TYPE string          ASSERT $value != NONE;
             DEFINE FIELD email              ON TABLE user TYPE string          ASSERT $value != NONE AND is::email($value);
             DEFINE FIELD profile_pic_uri    ON TABLE user TYPE string;
+            DEFINE FIELD comments_auto_close_days ON TABLE user TYPE int;
             DEFINE FIELD created_at         ON TABLE user TYPE datetime        ASSERT $value != NONE;
             DEFINE FIELD updated_at         ON TABLE user TYPE datetime;       
             DEFINE FIELD deleted_at         ON TABLE user TYPE datetime;       
@@ -38,6 +41,21 @@ impl Database {
         Ok(())
     }
 
+    pub async fn create_block_table(&self) -> Result<(), Error> {
+        let sql = r#"
+            DEFINE INDEX unique_blocks
+            ON TABLE blocks
+            COLUMNS in, out UNIQUE;
+        "#;
+
+        self.client.query(sql).await.map_err(|err| {
+            Error::DBCouldNotCreateTable(BLOCK_TBL_NAME.to_string(), err.to_string())
+        })?;
+        log::info!("Successfully create table: `{}`", BLOCK_TBL_NAME);
+
+        Ok(())
+    }
+
     pub async fn create_user(&self, info: &mut UserForCreate) -> Result<String, Error> {
         info.created_at = chrono::offset::Utc::now();
         let user: User = self
@@ -91,6 +109,10 @@ impl Database {
                 "/profile_pic_uri",
                 &new_user.profile_pic_uri,
             ))
+            .patch(PatchOp::replace(
+                "/comments_auto_close_days",
+                new_user.comments_auto_close_days,
+            ))
             .await
             .map_err(|err| Error::DBCouldNotUpdateRecord(id.to_string(), err.to_string()))?;
         log::debug!(
@@ -139,6 +161,84 @@ impl Database {
 
         Ok(users[0].clone())
     }
+
+    pub async fn get_user_with_username(&self, username: &str) -> Result<User, Error> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE username == $username",
+            USER_TBL_NAME
+        );
+        let users: Vec<User> = self
+            .client
+            .query(sql)
+            .bind(("username", username))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectRecord(username.to_string(), err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        users
+            .into_iter()
+            .next()
+            .ok_or(Error::DBRecordDidNotExist(username.to_string()))
+    }
+
+    // Soft deleted users are left out
+    pub async fn list_users_with_ids(&self, ids: &[Thing]) -> Result<Vec<User>, Error> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE id INSIDE $ids AND deleted = false",
+            USER_TBL_NAME
+        );
+        let users: Vec<User> = self
+            .client
+            .query(sql)
+            .bind(("ids", ids))
+            .await
+            .map_err(|err| Error::DBCouldNotSelectAllRecords(err.to_string()))?
+            .take(0)
+            .map_err(|err| Error::DBRecordEmpty(err.to_string()))?;
+
+        Ok(users)
+    }
+
+    pub async fn block_user(&self, user: &Thing, blocked: &Thing) -> Result<(), Error> {
+        let sql = format!(
+            "RELATE $user->{}->$blocked SET created_at = time::now()",
+            BLOCK_TBL_NAME
+        );
+        self.client
+            .query(sql)
+            .bind(("user", user))
+            .bind(("blocked", blocked))
+            .await
+            .map_err(|err| {
+                Error::DBCouldNotRelateRecord(
+                    user.to_string(),
+                    blocked.to_string(),
+                    err.to_string(),
+                )
+            })?
+            .check()
+            .map_err(|err| Error::DBRecordAlreadyExist(blocked.to_string(), err.to_string()))?;
+        log::debug!("Successfully blocked user: `{}` for: `{}`", blocked, user);
+
+        Ok(())
+    }
+
+    pub async fn unblock_user(&self, user: &Thing, blocked: &Thing) -> Result<(), Error> {
+        let sql = format!(
+            "DELETE {} WHERE in = $user AND out = $blocked",
+            BLOCK_TBL_NAME
+        );
+        self.client
+            .query(sql)
+            .bind(("user", user))
+            .bind(("blocked", blocked))
+            .await
+            .map_err(|err| Error::DBCouldNotDeleteRecord(blocked.to_string(), err.to_string()))?;
+        log::debug!("Successfully unblocked user: `{}` for: `{}`", blocked, user);
+
+        Ok(())
+    }
 }
 
 async fn filter_empty_field(
@@ -146,7 +246,10 @@ async fn filter_empty_field(
     old_user: &User,
     context: &Context,
 ) -> Result<UserForCreate, Error> {
-    if new_user.first_name.is_empty() && new_user.last_name.is_empty() && new_user.avatar.is_none()
+    if new_user.first_name.is_empty()
+        && new_user.last_name.is_empty()
+        && new_user.avatar.is_none()
+        && new_user.comments_auto_close_days.is_none()
     {
         return Err(Error::ServerEmptyFormFromUser);
     }
@@ -160,6 +263,10 @@ async fn filter_empty_field(
         deleted: old_user.deleted,
         avatar: Default::default(),
         profile_pic_uri: old_user.profile_pic_uri.clone(),
+        comments_auto_close_days: match new_user.comments_auto_close_days {
+            Some(days) => Some(days).filter(|days| *days > 0),
+            None => old_user.comments_auto_close_days,
+        },
         created_at: old_user.created_at,
         updated_at: old_user.updated_at,
         deleted_at: old_user.deleted_at,
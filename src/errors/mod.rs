@@ -7,6 +7,7 @@ use serde_json::json;
 
 // TODO: Maybe find a better way to handle error
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     // The last String will always be error message when map_err
     DBCouldNotOpenWebSocket(String, String),
@@ -27,6 +28,7 @@ pub enum Error {
     DBDuplicateUserEmail,
 
     ParseEnvFailedWrongFormat(String),
+    ParseEnvFailedMissingVar(String),
 
     ServerNoSuchIP(String, String),
     ServerCouldNotStart(String),
@@ -35,9 +37,13 @@ pub enum Error {
     ServerUnauthorizedUser,
     ServerEmptyFormFromUser,
     ServerUnsupportedMediaType(String),
+    ServerBadRequest(String),
+    ServerCommentsLocked(String, String),
 
     MinioCouldNotInitBucket(String, String),
     MinioCouldNotPutObject(String),
+    MinioCouldNotDeleteObject(String, String),
+    MinioCouldNotGetObject(String, String),
 
     JWTTokenCreationError(String),
     JWTTokenNotFoundOnHeader,
@@ -118,6 +124,10 @@ impl IntoResponse for Error {
             Error::ParseEnvFailedWrongFormat(error) => {
                 ("Could not parse env file.".to_string(), error)
             }
+            Error::ParseEnvFailedMissingVar(name) => (
+                "Could not parse env file.".to_string(),
+                format!("{} must be set", name),
+            ),
             Error::ServerNoSuchIP(address, error) => {
                 status_code = StatusCode::NOT_FOUND;
                 (
@@ -151,12 +161,27 @@ impl IntoResponse for Error {
                     "".to_string(),
                 )
             }
+            Error::ServerBadRequest(error) => {
+                status_code = StatusCode::BAD_REQUEST;
+                ("Could not process request".to_string(), error)
+            }
+            Error::ServerCommentsLocked(id, error) => {
+                status_code = StatusCode::FORBIDDEN;
+                (format!("Comments are closed on article: `{}`", id), error)
+            }
             Error::MinioCouldNotInitBucket(name, error) => {
                 (format!("Could not initialize bucket: `{}`", name), error)
             }
             Error::MinioCouldNotPutObject(error) => {
                 ("Could not upload object to s3".to_string(), error)
             }
+            Error::MinioCouldNotDeleteObject(path, error) => {
+                (format!("Could not delete object `{}` from s3", path), error)
+            }
+            Error::MinioCouldNotGetObject(path, error) => {
+                status_code = StatusCode::NOT_FOUND;
+                (format!("Could not get object `{}` from s3", path), error)
+            }
             Error::JWTTokenCreationError(error) => {
                 ("Could not create JWT token".to_string(), error)
             }
@@ -1,4 +1,6 @@
 use crate::database::user::USER_TBL_NAME;
+use crate::models::{comment::ModerationMode, reaction::ReactionCount};
+use crate::utils::image::Image;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -9,23 +11,69 @@ pub struct Article {
     pub id: Thing,
     pub user_id: Thing,
     pub title: String,
+    pub slug: String,
+    pub slug_history: Option<Vec<String>>,
     pub public: bool,
+    // Unlisted articles can be opened with a direct link but are left out of listings
+    #[serde(default)]
+    pub unlisted: bool,
+    // Set when the article got too many reports or by a moderator
+    #[serde(default)]
+    pub hidden: bool,
     pub article_uri: String,
-    pub tags: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub images_uri_list: Option<Vec<String>>,
+    pub cover_uri: Option<String>,
+    pub excerpt: Option<String>,
+    #[serde(default)]
+    pub excerpt_is_custom: bool,
+    pub description: Option<String>,
+    pub word_count: Option<usize>,
+    // In minutes
+    pub reading_time: Option<usize>,
+    pub toc: Option<Vec<TocEntry>>,
+    // Follows the site setting when missing
+    pub moderation: Option<ModerationMode>,
+    // New comments are rejected while locked or after `comments_closed_at`
+    #[serde(default)]
+    pub comments_locked: bool,
+    pub comments_closed_at: Option<DateTime<Utc>>,
     pub comments: Option<Vec<Thing>>,
-    pub liked_by: Option<Vec<Thing>>,
+    // Kept up to date by the events on `likes`
+    #[serde(default)]
+    pub like_count: usize,
+    pub reactions: Option<Vec<ReactionCount>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+// One heading of the article, `anchor` is the id of the heading in the stored HTML
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TocEntry {
+    pub level: usize,
+    pub title: String,
+    pub anchor: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ArticleForCreate {
     pub article_uri: String,
     pub user_id: Thing,
     pub title: String,
+    pub slug: String,
     pub public: bool,
+    pub unlisted: bool,
+    pub tags: Vec<String>,
+    pub excerpt: Option<String>,
+    pub excerpt_is_custom: bool,
+    pub description: Option<String>,
+    pub moderation: Option<ModerationMode>,
+    pub comments_locked: bool,
+    pub comments_closed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub cover: Option<Image>,
 }
 
 impl ArticleForCreate {
@@ -34,9 +82,47 @@ impl ArticleForCreate {
             article_uri: Default::default(),
             user_id: Thing::from((USER_TBL_NAME, "")),
             title: Default::default(),
+            slug: Default::default(),
             public: false,
+            unlisted: false,
+            tags: Default::default(),
+            excerpt: Default::default(),
+            excerpt_is_custom: false,
+            description: Default::default(),
+            moderation: Default::default(),
+            comments_locked: false,
+            comments_closed_at: Default::default(),
             created_at: Default::default(),
             updated_at: Default::default(),
+            cover: Default::default(),
         }
     }
 }
+
+#[derive(Debug, Default)]
+pub struct ArticleForUpdate {
+    pub title: Option<String>,
+    pub public: Option<bool>,
+    pub unlisted: Option<bool>,
+    pub tags: Option<Vec<String>>,
+    pub content: Option<String>,
+    pub excerpt: Option<String>,
+    pub description: Option<String>,
+    // `Some(None)` goes back to the site setting
+    pub moderation: Option<Option<ModerationMode>>,
+    pub comments_locked: Option<bool>,
+    // `Some(None)` reopens the comments
+    pub comments_closed_at: Option<Option<DateTime<Utc>>>,
+    pub cover: Option<Image>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedArticle {
+    pub id: Thing,
+    pub user_id: Thing,
+    pub title: String,
+    pub slug: String,
+    pub tags: Vec<String>,
+    pub score: f64,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,3 +1,4 @@
+use crate::models::{reaction::ReactionCount, spam::SpamLabel};
 use crate::utils::image::Image;
 
 use chrono::{DateTime, Utc};
@@ -10,21 +11,131 @@ pub struct Comment {
     pub user_id: Thing,
     pub article_id: Thing,
     pub deleted: bool,
+    // Comments written before moderation existed were all published
+    #[serde(default)]
+    pub status: CommentStatus,
+    // Set when the comment got too many reports or by a moderator
+    #[serde(default)]
+    pub hidden: bool,
+    // Between 0 and 1, set when the comment was checked by the spam filter
+    pub spam_score: Option<f64>,
+    // Set once a moderator approved the comment or marked it as spam
+    pub spam_label: Option<SpamLabel>,
     pub reply: Option<Vec<Thing>>,
     pub content: Option<String>,
+    // `content` is the Markdown source, this is what it renders to with mentions linked to the
+    // profile of the users
+    pub content_html: Option<String>,
     pub media_uri: Option<String>,
-    pub liked_by: Option<Vec<Thing>>,
+    // Kept up to date by the events on `likes`
+    #[serde(default)]
+    pub like_count: usize,
+    pub reactions: Option<Vec<ReactionCount>>,
+    // Earlier versions are kept in `comment_revision`
+    #[serde(default)]
+    pub edited: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub deleted_at: Option<DateTime<Utc>>,
 }
 
+impl Comment {
+    pub fn has_replies(&self) -> bool {
+        self.reply.as_ref().is_some_and(|reply| !reply.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentStatus {
+    Pending,
+    #[default]
+    Approved,
+    Rejected,
+    Spam,
+}
+
+impl std::fmt::Display for CommentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommentStatus::Pending => write!(f, "pending"),
+            CommentStatus::Approved => write!(f, "approved"),
+            CommentStatus::Rejected => write!(f, "rejected"),
+            CommentStatus::Spam => write!(f, "spam"),
+        }
+    }
+}
+
+// Who can comment on an article and whether it has to be approved first, articles without one
+// follow the site setting
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationMode {
+    Open,
+    // Only the first comment of a user waits for approval
+    FirstTime,
+    Always,
+    Closed,
+}
+
+impl ModerationMode {
+    pub fn from_str(mode: &str) -> Option<Self> {
+        match mode {
+            "open" => Some(ModerationMode::Open),
+            "first_time" => Some(ModerationMode::FirstTime),
+            "always" => Some(ModerationMode::Always),
+            "closed" => Some(ModerationMode::Closed),
+            _ => None,
+        }
+    }
+}
+
+// Deleted comments that still have replies keep their place in the thread, without what was
+// said or who said it
+#[derive(Debug, Serialize)]
+pub struct CommentTombstone {
+    pub id: Thing,
+    pub article_id: Thing,
+    pub deleted: bool,
+    pub reply: Option<Vec<Thing>>,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// What readers get to see of a comment
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum CommentEntry {
+    Comment(Comment),
+    Tombstone(CommentTombstone),
+}
+
+impl From<Comment> for CommentEntry {
+    fn from(comment: Comment) -> Self {
+        if !comment.deleted {
+            return CommentEntry::Comment(comment);
+        }
+
+        CommentEntry::Tombstone(CommentTombstone {
+            id: comment.id,
+            article_id: comment.article_id,
+            deleted: true,
+            reply: comment.reply,
+            created_at: comment.created_at,
+            deleted_at: comment.deleted_at,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CommentForCreate {
     pub user_id: Thing,
     pub article_id: Thing,
     pub deleted: bool,
+    pub status: CommentStatus,
+    pub spam_score: Option<f64>,
     pub content: Option<String>,
+    pub content_html: Option<String>,
     pub media_uri: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
@@ -37,7 +148,10 @@ impl CommentForCreate {
             user_id: Thing::from(("", "")),
             article_id: Thing::from(("", "")),
             deleted: false,
+            status: Default::default(),
+            spam_score: Default::default(),
             content: Default::default(),
+            content_html: Default::default(),
             media_uri: Default::default(),
             created_at: Default::default(),
             updated_at: Default::default(),
@@ -45,3 +159,84 @@ impl CommentForCreate {
         }
     }
 }
+
+// A version of a comment that was replaced by an edit
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommentRevision {
+    pub id: Thing,
+    pub comment_id: Thing,
+    pub content: Option<String>,
+    pub media_uri: Option<String>,
+    // When this version was written
+    pub created_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentRevisionForCreate {
+    pub comment_id: Thing,
+    pub content: Option<String>,
+    pub media_uri: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentView {
+    #[default]
+    Flat,
+    Tree,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentSort {
+    #[default]
+    Oldest,
+    Newest,
+    MostLiked,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CommentQuery {
+    #[serde(default)]
+    pub view: CommentView,
+    #[serde(default)]
+    pub sort: CommentSort,
+    // Levels of replies to include below the requested level
+    pub depth: Option<usize>,
+    // Comments per level, the requested level can be paged further with `offset` in the tree
+    // view or with `cursor` in the flat view
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentNode {
+    #[serde(flatten)]
+    pub comment: CommentEntry,
+    pub reply_count: usize,
+    // Set when only part of the replies were included, the rest can be loaded from
+    // `/api/comments/reply/:comment_id?view=tree&offset=<number of replies included>`
+    pub has_more_replies: bool,
+    pub replies: Vec<CommentNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentPage {
+    pub comments: Vec<CommentEntry>,
+    pub total: usize,
+    // Pass it as `cursor` to get the next page, missing on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModerationQuery {
+    // Pending comments when missing
+    pub status: Option<CommentStatus>,
+    pub article_id: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
@@ -0,0 +1,42 @@
+use crate::models::{article::Article, comment::Comment};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+// A user who liked an article or a comment
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Liker {
+    pub id: Thing,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    // Url of the profile picture
+    pub avatar: Option<String>,
+    // Likes given before it was recorded have none
+    pub liked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", content = "item", rename_all = "lowercase")]
+pub enum LikedContent {
+    Article(Article),
+    Comment(Comment),
+}
+
+// Something a user liked
+#[derive(Debug, Serialize)]
+pub struct LikedItem {
+    #[serde(flatten)]
+    pub content: LikedContent,
+    pub liked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LikeQuery {
+    // `article` or `comment`, only used when listing the likes of a user
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
@@ -1,3 +1,11 @@
 pub mod article;
 pub mod comment;
+pub mod like;
+pub mod notification;
+pub mod reaction;
+pub mod report;
+pub mod search;
+pub mod series;
+pub mod spam;
+pub mod tag;
 pub mod user;
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    Mention,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Thing,
+    // Who gets notified
+    pub user_id: Thing,
+    pub kind: NotificationKind,
+    // Who caused it
+    pub actor_id: Thing,
+    // The comment or article it is about
+    pub source: Thing,
+    pub article_id: Thing,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationForCreate {
+    pub user_id: Thing,
+    pub kind: NotificationKind,
+    pub actor_id: Thing,
+    pub source: Thing,
+    pub article_id: Thing,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationQuery {
+    #[serde(default)]
+    pub unread: bool,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
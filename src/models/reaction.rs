@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+// How many users reacted to an article or a comment with `reaction`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionCount {
+    pub reaction: String,
+    pub count: usize,
+}
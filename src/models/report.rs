@@ -0,0 +1,120 @@
+use crate::database::user::USER_TBL_NAME;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    HateSpeech,
+    Violence,
+    SexualContent,
+    Misinformation,
+    Other,
+}
+
+impl ReportReason {
+    pub fn from_str(reason: &str) -> Option<Self> {
+        match reason {
+            "spam" => Some(ReportReason::Spam),
+            "harassment" => Some(ReportReason::Harassment),
+            "hate_speech" => Some(ReportReason::HateSpeech),
+            "violence" => Some(ReportReason::Violence),
+            "sexual_content" => Some(ReportReason::SexualContent),
+            "misinformation" => Some(ReportReason::Misinformation),
+            "other" => Some(ReportReason::Other),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    #[default]
+    Open,
+    Resolved,
+}
+
+// What the moderator did about the reported content
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportAction {
+    // Nothing wrong with it, hidden content is shown again
+    Dismiss,
+    Hide,
+    Remove,
+}
+
+impl ReportAction {
+    pub fn from_str(action: &str) -> Option<Self> {
+        match action {
+            "dismiss" => Some(ReportAction::Dismiss),
+            "hide" => Some(ReportAction::Hide),
+            "remove" => Some(ReportAction::Remove),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Report {
+    pub id: Thing,
+    pub reporter_id: Thing,
+    // A comment, an article or a user
+    pub target: Thing,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub status: ReportStatus,
+    pub assigned_to: Option<Thing>,
+    pub action: Option<ReportAction>,
+    pub note: Option<String>,
+    pub resolved_by: Option<Thing>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportForCreate {
+    pub reporter_id: Thing,
+    pub target: Thing,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub status: ReportStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReportForCreate {
+    pub fn new() -> Self {
+        ReportForCreate {
+            reporter_id: Thing::from((USER_TBL_NAME, "")),
+            target: Thing::from(("", "")),
+            reason: ReportReason::Other,
+            details: Default::default(),
+            status: Default::default(),
+            created_at: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReportResolution {
+    pub action: ReportAction,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    // Open reports when missing
+    pub status: Option<ReportStatus>,
+    pub reason: Option<ReportReason>,
+    // `comment`, `article` or `user`
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub assigned_to: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Article,
+    Comment,
+}
+
+// One entry of the search index, either an article or a comment
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchDocument {
+    pub kind: SearchKind,
+    pub record: Thing,
+    pub article_id: Thing,
+    pub user_id: Thing,
+    pub title: Option<String>,
+    pub body: String,
+    pub terms: Vec<String>,
+    // Every token of the body followed by the title `TITLE_WEIGHT` times, scoring counts the
+    // matches in there. Documents indexed before it existed need a reindex
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    pub length: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    #[serde(rename = "type")]
+    pub kind: Option<SearchKind>,
+    // Username of the author
+    pub author: Option<String>,
+    pub tag: Option<String>,
+    pub page: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    #[serde(rename = "type")]
+    pub kind: SearchKind,
+    pub id: Thing,
+    pub article_id: Thing,
+    pub user_id: Thing,
+    pub title: Option<String>,
+    pub snippet: String,
+    pub score: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    User,
+    Tag,
+    Article,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    #[serde(rename = "type")]
+    pub kind: SuggestionKind,
+    pub id: Thing,
+    pub label: String,
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestQuery {
+    pub q: String,
+    // Comma separated list of `user`, `tag` and `article`, defaults to all of them
+    pub types: Option<String>,
+    pub limit: Option<usize>,
+}
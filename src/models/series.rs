@@ -0,0 +1,57 @@
+use crate::database::user::USER_TBL_NAME;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Series {
+    pub id: Thing,
+    pub user_id: Thing,
+    pub title: String,
+    pub description: Option<String>,
+    pub articles: Option<Vec<Thing>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeriesForCreate {
+    pub user_id: Thing,
+    pub title: String,
+    pub description: Option<String>,
+    pub articles: Vec<Thing>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl SeriesForCreate {
+    pub fn new() -> Self {
+        SeriesForCreate {
+            user_id: Thing::from((USER_TBL_NAME, "")),
+            title: Default::default(),
+            description: Default::default(),
+            articles: Default::default(),
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeriesEntry {
+    pub id: Thing,
+    pub title: String,
+    pub slug: String,
+}
+
+// Where an article sits inside a series, e.g. part 2 of 5
+#[derive(Debug, Serialize)]
+pub struct SeriesNavigation {
+    pub id: Thing,
+    pub title: String,
+    pub part: usize,
+    pub total: usize,
+    pub previous: Option<SeriesEntry>,
+    pub next: Option<SeriesEntry>,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+// What a moderator decided a comment was, used to train the spam filter
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpamLabel {
+    Spam,
+    Ham,
+}
+
+// Number of spam and ham documents, either in total or containing a given token
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+pub struct SpamCounts {
+    #[serde(default)]
+    pub spam: usize,
+    #[serde(default)]
+    pub ham: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpamToken {
+    pub id: Thing,
+    #[serde(flatten)]
+    pub counts: SpamCounts,
+}
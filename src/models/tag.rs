@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: Thing,
+    pub name: String,
+    pub slug: String,
+    pub count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagForCreate {
+    pub name: String,
+    pub slug: String,
+}
+
+impl TagForCreate {
+    // Tags are case-insensitive: `Rust`, ` rust ` and `RUST` all end up as `rust`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+            .to_lowercase();
+        let slug = slug::slugify(&name);
+        if slug.is_empty() {
+            return None;
+        }
+
+        Some(TagForCreate { name, slug })
+    }
+
+    pub fn from_names(names: &[String]) -> Vec<Self> {
+        let mut tags: Vec<TagForCreate> = Vec::new();
+        for tag in names
+            .iter()
+            .filter_map(|name| TagForCreate::from_name(name))
+        {
+            if !tags.iter().any(|element| element.slug == tag.slug) {
+                tags.push(tag);
+            }
+        }
+
+        tags
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagForUpdate {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagForMerge {
+    pub into: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn from_name_normalizes_case_and_whitespace() {
+        let tag = TagForCreate::from_name("  Web   Development ").unwrap();
+
+        assert_eq!(tag.name, "web development");
+        assert_eq!(tag.slug, "web-development");
+    }
+
+    #[test]
+    fn from_name_rejects_names_without_slug() {
+        assert_eq!(TagForCreate::from_name("   "), None);
+        assert_eq!(TagForCreate::from_name("!!!"), None);
+    }
+
+    #[test]
+    fn from_names_drops_duplicates_and_keeps_order() {
+        let tags = TagForCreate::from_names(&names(&["Rust", "web", " rust ", "RUST", "", "Web"]));
+        let slugs: Vec<&str> = tags.iter().map(|tag| tag.slug.as_str()).collect();
+
+        assert_eq!(slugs, ["rust", "web"]);
+    }
+}
@@ -15,6 +15,8 @@ pub struct User {
     pub is_admin: bool,
     pub deleted: bool,
     pub profile_pic_uri: Option<String>,
+    // Comments on the user's articles close this many days after publication
+    pub comments_auto_close_days: Option<u32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub deleted_at: Option<DateTime<Utc>>,
@@ -31,6 +33,8 @@ pub struct UserForCreate {
     pub deleted: bool,
     pub avatar: Option<Image>,
     pub profile_pic_uri: Option<String>,
+    // 0 turns the policy off
+    pub comments_auto_close_days: Option<u32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub deleted_at: Option<DateTime<Utc>>,
@@ -47,6 +51,7 @@ impl UserForCreate {
             deleted: false,
             avatar: Default::default(),
             profile_pic_uri: Default::default(),
+            comments_auto_close_days: Default::default(),
             created_at: Default::default(),
             updated_at: Default::default(),
             deleted_at: Default::default(),
@@ -55,6 +60,7 @@ impl UserForCreate {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct UserForLogin {
     pub email: String,
     pub password: String,
@@ -12,5 +12,13 @@ pub fn routes(database: Arc<Database>) -> Router {
         .nest("/api", routes::login::routes(database.clone()))
         .nest("/api", routes::comment::routes(database.clone()))
         .nest("/api", routes::like::routes(database.clone()))
-        .nest("/api", routes::article::routes(database))
+        .nest("/api", routes::reaction::routes(database.clone()))
+        .nest("/api", routes::tag::routes(database.clone()))
+        .nest("/api", routes::series::routes(database.clone()))
+        .nest("/api", routes::feed::routes(database.clone()))
+        .nest("/api", routes::search::routes(database.clone()))
+        .nest("/api", routes::report::routes(database.clone()))
+        .nest("/api", routes::article::routes(database.clone()))
+        .nest("/api", routes::article::author_routes(database.clone()))
+        .merge(routes::sitemap::routes(database))
 }
@@ -1,27 +1,31 @@
 use crate::database::{
-    article::{ARTICLE_FOLDER, ARTICLE_TBL_NAME},
+    article::{ArticleSource, ARTICLE_FOLDER, ARTICLE_TBL_NAME},
     user::USER_TBL_NAME,
     Database,
 };
 use crate::errors::Error;
 use crate::routes;
-use crate::server::context::Context;
+use crate::server::{config::SiteConfig, context::Context};
 use crate::utils;
 
 use axum::{
     extract::{Multipart, Path, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
-    routing::{get, patch},
+    routing::{get, post},
     Json, Router,
 };
 use serde_json::json;
 use std::sync::Arc;
 use surrealdb::sql::Thing;
 
+const RELATED_ARTICLES_LENGTH: usize = 5;
+
 pub fn routes(database: Arc<Database>) -> Router {
     Router::new()
         .route("/articles/:article_id", get(get_article_with_id))
+        .route("/articles/:article_id/meta", get(get_article_meta))
+        .route("/articles/:article_id/related", get(list_related_articles))
         .with_state(database.clone())
         .nest(
             "/articles/:article_id",
@@ -29,13 +33,23 @@ pub fn routes(database: Arc<Database>) -> Router {
         )
 }
 
+pub fn author_routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/u/:username", get(list_author_articles))
+        .with_state(database)
+}
+
 pub fn for_user_routes(database: Arc<Database>) -> Router {
     Router::new()
         .route("/articles", get(list_articles).post(create_article))
+        // Reads go through the author's username and the article slug, writes through their ids
         .route(
             "/articles/:article_id",
-            patch(update_article).delete(delete_article),
+            get(get_article_with_slug)
+                .patch(update_article)
+                .delete(delete_article),
         )
+        .route("/articles/:article_id/images", post(upload_article_images))
         .with_state(database)
 }
 
@@ -61,6 +75,31 @@ async fn list_articles(
     Ok(res)
 }
 
+// Public author page, only lists what anyone could read
+async fn list_author_articles(
+    State(database): State<Arc<Database>>,
+    Path(username): Path<String>,
+) -> Result<Response, Error> {
+    let user = database.get_user_with_username(&username).await?;
+    if user.deleted {
+        return Err(Error::DBRecordDidNotExist(username));
+    }
+    let articles = database
+        .list_public_articles(&ArticleSource::User(user.id), None)
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully list articles of `{}`", user.username)
+        },
+        "articles": articles
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
 #[axum_macros::debug_handler]
 async fn create_article(
     context: Context,
@@ -80,6 +119,11 @@ async fn create_article(
     )
     .await?;
     database.update_article_uri(&article_id, &file_path).await?;
+    if let Some(cover) = &article.cover {
+        database
+            .update_article_cover(&article.user_id, &article_id, cover)
+            .await?;
+    }
 
     let body = Json(json!({
         "result": {
@@ -105,7 +149,10 @@ async fn update_article(
     let article = database
         .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
         .await?;
-    utils::multipart::parse_article_for_update(payload, &article.article_uri).await?;
+    context.check_permissions(Some(article.user_id.clone()), false)?;
+
+    let info = utils::multipart::parse_article_for_update(payload, &article.article_uri).await?;
+    database.update_article(&article, &info).await?;
 
     let body = Json(json!({
         "result": {
@@ -119,25 +166,129 @@ async fn update_article(
 }
 
 async fn get_article_with_id(
+    context: Option<Context>,
     State(database): State<Arc<Database>>,
     Path(article_id): Path<String>,
 ) -> Result<Response, Error> {
     let article = database
         .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
         .await?;
+    database.check_article_visible(&article, &context)?;
+    let series = database
+        .get_series_navigation_for_article(&article.id, &context)
+        .await?;
 
     let body = Json(json!({
         "result": {
             "success": true,
             "message": "Successfully get article.",
         },
-        "article": article
+        "article": article,
+        "series": series
     }));
     let res = (StatusCode::CREATED, body).into_response();
 
     Ok(res)
 }
 
+// Permalinks are `/users/:username/articles/:slug`, old slugs are redirected to the current one
+async fn get_article_with_slug(
+    context: Option<Context>,
+    State(database): State<Arc<Database>>,
+    Path((username, slug)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let user = database.get_user_with_username(&username).await?;
+    let article = database.get_article_with_slug(&user.id, &slug).await?;
+    database.check_article_visible(&article, &context)?;
+
+    if article.slug != slug {
+        let location = format!("/api/users/{}/articles/{}", user.username, article.slug);
+        let body = Json(json!({
+            "result": {
+                "success": true,
+                "message": format!("Article has moved to `{}`", location),
+            },
+        }));
+        let res = (
+            StatusCode::MOVED_PERMANENTLY,
+            [(header::LOCATION, location)],
+            body,
+        )
+            .into_response();
+
+        return Ok(res);
+    }
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get article.",
+        },
+        "article": article
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+// Lets the frontend and link unfurlers render previews without parsing the article itself
+async fn get_article_meta(
+    context: Option<Context>,
+    State(database): State<Arc<Database>>,
+    Path(article_id): Path<String>,
+) -> Result<Response, Error> {
+    let article = database
+        .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
+        .await?;
+    database.check_article_visible(&article, &context)?;
+    let author = database.get_user_with_id(&article.user_id).await?;
+    let site = SiteConfig::parse_from_env_file()?;
+
+    let meta = utils::meta::social_meta_for_article(&article, &author, &site)?;
+    let html = meta
+        .iter()
+        .map(|tag| tag.render())
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get article meta tags.",
+        },
+        "meta": meta,
+        "html": html
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn list_related_articles(
+    context: Option<Context>,
+    State(database): State<Arc<Database>>,
+    Path(article_id): Path<String>,
+) -> Result<Response, Error> {
+    let article = database
+        .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
+        .await?;
+    database.check_article_visible(&article, &context)?;
+    let related = database
+        .list_related_articles(&article, RELATED_ARTICLES_LENGTH)
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully list related articles.",
+        },
+        "articles": related
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
 async fn delete_article(
     context: Context,
     State(database): State<Arc<Database>>,
@@ -146,9 +297,11 @@ async fn delete_article(
     let user_id = Thing::from((USER_TBL_NAME, user_id.as_str()));
     context.check_permissions(Some(user_id), false)?;
 
-    let article = database
-        .delete_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
-        .await?;
+    let article_id = Thing::from((ARTICLE_TBL_NAME, article_id.as_str()));
+    let article = database.get_article_with_id(&article_id).await?;
+    context.check_permissions(Some(article.user_id.clone()), false)?;
+
+    let article = database.delete_article_with_id(&article_id).await?;
 
     let body = Json(json!({
         "result": {
@@ -161,3 +314,32 @@ async fn delete_article(
 
     Ok(res)
 }
+
+async fn upload_article_images(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((user_id, article_id)): Path<(String, String)>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    let user_id = Thing::from((USER_TBL_NAME, user_id.as_str()));
+    context.check_permissions(Some(user_id), false)?;
+
+    let article = database
+        .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
+        .await?;
+    context.check_permissions(Some(article.user_id.clone()), false)?;
+
+    let images = utils::multipart::parse_images(payload, "image").await?;
+    let uris = database.add_article_images(&article, &images).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully uploaded images.",
+        },
+        "images_uri_list": uris
+    }));
+    let res = (StatusCode::CREATED, body).into_response();
+
+    Ok(res)
+}
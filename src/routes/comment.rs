@@ -4,11 +4,14 @@ use crate::database::{
     Database,
 };
 use crate::errors::Error;
+use crate::models::comment::{
+    CommentEntry, CommentQuery, CommentStatus, CommentView, ModerationQuery,
+};
 use crate::server::context::Context;
 use crate::utils;
 
 use axum::{
-    extract::{Multipart, Path, State},
+    extract::{Multipart, Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::{get, post},
@@ -24,7 +27,12 @@ pub fn routes(database: Arc<Database>) -> Router {
             "/comments/:comment_id",
             get(get_comment).put(update_comment).delete(delete_comment),
         )
+        .route("/comments/:comment_id/history", get(get_comment_history))
         .route("/comments/reply/:comment_id", get(get_reply_for_comment))
+        .route("/comments/moderation", get(get_moderation_queue))
+        .route("/comments/moderation/approve", post(approve_comments))
+        .route("/comments/moderation/reject", post(reject_comments))
+        .route("/comments/moderation/spam", post(mark_comments_as_spam))
         .with_state(database)
 }
 
@@ -47,6 +55,13 @@ async fn get_comment(
 ) -> Result<Response, Error> {
     let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
     let comment = database.get_comment(&comment_id).await?;
+    if comment.status != CommentStatus::Approved
+        || comment.hidden
+        || (comment.deleted && !comment.has_replies())
+    {
+        return Err(Error::DBRecordDidNotExist(comment_id.to_string()));
+    }
+    let comment = CommentEntry::from(comment);
 
     let body = Json(json!({
         "result": {
@@ -60,19 +75,70 @@ async fn get_comment(
     Ok(res)
 }
 
+// Visible to the author of the comment and to the ones who can moderate it, only to the latter
+// once the comment is deleted
+async fn get_comment_history(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(comment_id): Path<String>,
+) -> Result<Response, Error> {
+    let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
+    let comment = database.get_comment(&comment_id).await?;
+    if comment.deleted || context.user_id != comment.user_id {
+        let article = database.get_article_with_id(&comment.article_id).await?;
+        context.check_permissions(Some(article.user_id), false)?;
+    }
+    let revisions = database.list_comment_revisions(&comment_id).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get comment history"
+        },
+        "comment": comment,
+        "revisions": revisions
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
 async fn get_reply_for_comment(
     State(database): State<Arc<Database>>,
     Path(comment_id): Path<String>,
+    Query(query): Query<CommentQuery>,
 ) -> Result<Response, Error> {
     let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
-    let reply = database.get_reply_for_comment(&comment_id).await?;
+    if query.view == CommentView::Tree {
+        let comment = database.get_comment(&comment_id).await?;
+        let (total, reply) = database
+            .get_comment_tree(&comment.article_id, Some(&comment_id), &query)
+            .await?;
+
+        let body = Json(json!({
+            "result": {
+                "success": true,
+                "message": format!("Successfully get reply for comment with id: `{}`", comment_id)
+            },
+            "total": total,
+            "reply": reply
+        }));
+        let res = (StatusCode::OK, body).into_response();
+
+        return Ok(res);
+    }
+
+    let comment = database.get_comment(&comment_id).await?;
+    let page = database.get_reply_for_comment(&comment, &query).await?;
 
     let body = Json(json!({
         "result": {
             "success": true,
             "message": format!("Successfully get reply for comment with id: `{}`", comment_id)
         },
-        "reply": reply
+        "total": page.total,
+        "next_cursor": page.next_cursor,
+        "reply": page.comments
     }));
     let res = (StatusCode::OK, body).into_response();
 
@@ -82,16 +148,37 @@ async fn get_reply_for_comment(
 async fn get_comment_for_article(
     State(database): State<Arc<Database>>,
     Path(article_id): Path<String>,
+    Query(query): Query<CommentQuery>,
 ) -> Result<Response, Error> {
     let article_id = Thing::from((ARTICLE_TBL_NAME, article_id.as_str()));
-    let comments = database.get_comment_for_article(&article_id).await?;
+    if query.view == CommentView::Tree {
+        let (total, comments) = database.get_comment_tree(&article_id, None, &query).await?;
+
+        let body = Json(json!({
+            "result": {
+                "success": true,
+                "message": "Successfully get comments"
+            },
+            "total": total,
+            "comments": comments
+        }));
+        let res = (StatusCode::OK, body).into_response();
+
+        return Ok(res);
+    }
+
+    let page = database
+        .get_comment_for_article(&article_id, &query)
+        .await?;
 
     let body = Json(json!({
         "result": {
             "success": true,
             "message": "Successfully get comments"
         },
-        "comments": comments
+        "total": page.total,
+        "next_cursor": page.next_cursor,
+        "comments": page.comments
     }));
     let res = (StatusCode::OK, body).into_response();
 
@@ -107,9 +194,13 @@ async fn create_comment(
 ) -> Result<Response, Error> {
     context.check_permissions(None, false)?;
     let article_id = Thing::from((ARTICLE_TBL_NAME, article_id.as_str()));
+    let article = database.get_article_with_id(&article_id).await?;
 
     let mut comment =
         utils::multipart::parse_comment_for_create(payload, &context, &article_id).await?;
+    database
+        .moderate_comment(&context, &article, &mut comment)
+        .await?;
     let comment_id = database.create_comment(&mut comment).await?;
 
     if let Some(media) = comment.image {
@@ -131,7 +222,8 @@ async fn create_comment(
             "success": true,
             "message": "Successfully create comment"
         },
-        "comment_id": comment_id
+        "comment_id": comment_id,
+        "status": comment.status
     }));
     let res = (StatusCode::CREATED, body).into_response();
 
@@ -148,9 +240,18 @@ async fn create_reply(
     let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
 
     context.check_permissions(None, false)?;
+    let article = database.get_article_with_id(&article_id).await?;
+    let parent = database.get_comment(&comment_id).await?;
+    if parent.article_id != article_id || parent.status != CommentStatus::Approved || parent.hidden
+    {
+        return Err(Error::DBRecordDidNotExist(comment_id.to_string()));
+    }
 
     let mut comment =
         utils::multipart::parse_comment_for_create(payload, &context, &article_id).await?;
+    database
+        .moderate_comment(&context, &article, &mut comment)
+        .await?;
     let reply = database
         .create_reply(&context, &(article_id, comment_id), &mut comment)
         .await?;
@@ -160,7 +261,8 @@ async fn create_reply(
             "success": true,
             "message": "Successfully create reply"
         },
-        "reply_id": reply
+        "reply_id": reply,
+        "status": comment.status
     }));
     let res = (StatusCode::CREATED, body).into_response();
 
@@ -176,18 +278,26 @@ async fn update_comment(
     let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
     let old_comment = database.get_comment(&comment_id).await?;
     context.check_permissions(Some(old_comment.user_id.clone()), false)?;
+    if old_comment.deleted {
+        return Err(Error::DBRecordDidNotExist(comment_id.to_string()));
+    }
+
+    let article = database
+        .get_article_with_id(&old_comment.article_id)
+        .await?;
 
     let mut new_comment =
         utils::multipart::parse_comment_for_create(payload, &context, &old_comment.article_id)
             .await?;
-    database
-        .update_comment(&old_comment, &mut new_comment)
+    let status = database
+        .update_comment(&context, &article, &old_comment, &mut new_comment)
         .await?;
     let body = Json(json!({
         "result": {
             "success": true,
             "message": "Successfully update comment"
         },
+        "status": status
     }));
     let res = (StatusCode::OK, body).into_response();
 
@@ -202,7 +312,7 @@ async fn delete_comment(
     let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
     let comment = database.get_comment(&comment_id).await?;
     context.check_permissions(Some(comment.user_id.clone()), false)?;
-    database.delete_comment(&comment_id).await?;
+    database.delete_comment(&comment).await?;
 
     let body = Json(json!({
         "result": {
@@ -214,3 +324,96 @@ async fn delete_comment(
 
     Ok(res)
 }
+
+// Admins see the whole site, article authors only the comments on their articles
+async fn get_moderation_queue(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Query(query): Query<ModerationQuery>,
+) -> Result<Response, Error> {
+    let article_id = match &query.article_id {
+        Some(article_id) => {
+            let article = database
+                .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
+                .await?;
+            context.check_permissions(Some(article.user_id), false)?;
+            Some(article.id)
+        }
+        None => {
+            context.check_permissions(None, true)?;
+            None
+        }
+    };
+    let (total, comments) = database
+        .get_moderation_queue(&query, article_id.as_ref())
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get moderation queue"
+        },
+        "total": total,
+        "comments": comments
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn approve_comments(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    moderate_comments(context, database, payload, CommentStatus::Approved).await
+}
+
+async fn reject_comments(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    moderate_comments(context, database, payload, CommentStatus::Rejected).await
+}
+
+async fn mark_comments_as_spam(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    moderate_comments(context, database, payload, CommentStatus::Spam).await
+}
+
+async fn moderate_comments(
+    context: Context,
+    database: Arc<Database>,
+    payload: Multipart,
+    status: CommentStatus,
+) -> Result<Response, Error> {
+    context.check_permissions(None, false)?;
+    let comment_ids: Vec<Thing> = utils::multipart::parse_id_list(payload, "comments")
+        .await?
+        .iter()
+        .map(|id| Thing::from((COMMENT_TBL_NAME, id.as_str())))
+        .collect();
+    if comment_ids.is_empty() {
+        return Err(Error::ServerBadRequest(String::from(
+            "At least one comment must be given in `comments`",
+        )));
+    }
+    let updated = database
+        .set_comment_status(&context, &comment_ids, status)
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully mark {} comment(s) as {}", updated.len(), status)
+        },
+        "comment_ids": updated
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
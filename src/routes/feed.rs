@@ -0,0 +1,406 @@
+use crate::database::{article::ArticleSource, user::USER_TBL_NAME, Database};
+use crate::errors::Error;
+use crate::models::{article::Article, user::User};
+use crate::s3;
+use crate::server::config::SiteConfig;
+use crate::utils::{
+    feed::{self, Feed, FeedItem, FEED_LENGTH},
+    html, markdown,
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Deserialize)]
+struct FeedQuery {
+    // Either `full` or `excerpt`, defaults to `excerpt`
+    content: Option<String>,
+}
+
+impl FeedQuery {
+    fn is_full_content(&self) -> bool {
+        self.content.as_deref() == Some("full")
+    }
+}
+
+enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+pub fn routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/feeds/articles.rss", get(site_rss))
+        .route("/feeds/articles.atom", get(site_atom))
+        .route("/feeds/articles.json", get(site_json))
+        .route("/users/:user_id/feed.rss", get(user_rss))
+        .route("/users/:user_id/feed.atom", get(user_atom))
+        .route("/users/:user_id/feed.json", get(user_json))
+        .route("/tags/:slug/feed.rss", get(tag_rss))
+        .route("/tags/:slug/feed.atom", get(tag_atom))
+        .with_state(database)
+}
+
+async fn site_rss(
+    State(database): State<Arc<Database>>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    serve_feed(
+        &database,
+        ArticleSource::Site,
+        "/api/feeds/articles.rss",
+        query.is_full_content(),
+        FeedFormat::Rss,
+        &headers,
+    )
+    .await
+}
+
+async fn site_atom(
+    State(database): State<Arc<Database>>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    serve_feed(
+        &database,
+        ArticleSource::Site,
+        "/api/feeds/articles.atom",
+        query.is_full_content(),
+        FeedFormat::Atom,
+        &headers,
+    )
+    .await
+}
+
+// JSON Feed items always carry the full article in `content_html`
+async fn site_json(
+    State(database): State<Arc<Database>>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    serve_feed(
+        &database,
+        ArticleSource::Site,
+        "/api/feeds/articles.json",
+        true,
+        FeedFormat::Json,
+        &headers,
+    )
+    .await
+}
+
+async fn user_rss(
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let path = format!("/api/users/{}/feed.rss", user_id);
+    let source = ArticleSource::User(Thing::from((USER_TBL_NAME, user_id.as_str())));
+    serve_feed(
+        &database,
+        source,
+        &path,
+        query.is_full_content(),
+        FeedFormat::Rss,
+        &headers,
+    )
+    .await
+}
+
+async fn user_atom(
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let path = format!("/api/users/{}/feed.atom", user_id);
+    let source = ArticleSource::User(Thing::from((USER_TBL_NAME, user_id.as_str())));
+    serve_feed(
+        &database,
+        source,
+        &path,
+        query.is_full_content(),
+        FeedFormat::Atom,
+        &headers,
+    )
+    .await
+}
+
+async fn user_json(
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let path = format!("/api/users/{}/feed.json", user_id);
+    let source = ArticleSource::User(Thing::from((USER_TBL_NAME, user_id.as_str())));
+    serve_feed(&database, source, &path, true, FeedFormat::Json, &headers).await
+}
+
+async fn tag_rss(
+    State(database): State<Arc<Database>>,
+    Path(slug): Path<String>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let path = format!("/api/tags/{}/feed.rss", slug);
+    serve_feed(
+        &database,
+        ArticleSource::Tag(slug),
+        &path,
+        query.is_full_content(),
+        FeedFormat::Rss,
+        &headers,
+    )
+    .await
+}
+
+async fn tag_atom(
+    State(database): State<Arc<Database>>,
+    Path(slug): Path<String>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let path = format!("/api/tags/{}/feed.atom", slug);
+    serve_feed(
+        &database,
+        ArticleSource::Tag(slug),
+        &path,
+        query.is_full_content(),
+        FeedFormat::Atom,
+        &headers,
+    )
+    .await
+}
+
+// Whether the client is up to date is decided from the articles alone, so a `304 Not Modified`
+// never has to fetch the article bodies
+async fn serve_feed(
+    database: &Database,
+    source: ArticleSource,
+    path: &str,
+    full_content: bool,
+    format: FeedFormat,
+    headers: &HeaderMap,
+) -> Result<Response, Error> {
+    let site = SiteConfig::parse_from_env_file()?;
+    let (title, description) = match &source {
+        ArticleSource::Site => (
+            site.name.clone(),
+            format!("Latest articles on {}", site.name),
+        ),
+        ArticleSource::User(user_id) => {
+            let user = database.get_user_with_id(user_id).await?;
+            (
+                format!("{} - {} {}", site.name, user.first_name, user.last_name),
+                format!("Latest articles by {}", user.username),
+            )
+        }
+        ArticleSource::Tag(slug) => {
+            let tag = database.get_tag(slug).await?;
+            (
+                format!("{} - #{}", site.name, tag.name),
+                format!("Latest articles tagged `{}`", tag.name),
+            )
+        }
+    };
+
+    let articles = database
+        .list_public_articles(&source, Some(FEED_LENGTH))
+        .await?;
+    let updated = articles
+        .iter()
+        .map(|article| article.updated_at.unwrap_or(article.created_at))
+        .max()
+        .unwrap_or(Utc.timestamp_opt(0, 0).unwrap());
+    let version = articles.iter().fold(
+        format!("{}\n{}\n{}\n{}", path, full_content, title, description),
+        |version, article| {
+            format!(
+                "{}\n{}@{}",
+                version,
+                article.id,
+                article
+                    .updated_at
+                    .unwrap_or(article.created_at)
+                    .to_rfc3339()
+            )
+        },
+    );
+    let etag = etag_of(&version);
+    if is_not_modified(headers, &etag, updated) {
+        return Ok(not_modified_response(etag, updated));
+    }
+
+    let mut authors: HashMap<String, User> = HashMap::new();
+    let mut items = Vec::new();
+    for article in articles {
+        let key = article.user_id.to_string();
+        if !authors.contains_key(&key) {
+            let user = database.get_user_with_id(&article.user_id).await?;
+            authors.insert(key.clone(), user);
+        }
+        let author = &authors[&key];
+        items.push(build_feed_item(&site, &article, author, full_content).await?);
+    }
+
+    let feed = Feed {
+        title,
+        description,
+        link: site.url.clone(),
+        feed_url: format!("{}{}", site.url, path),
+        updated,
+        items,
+    };
+    let (body, content_type) = match format {
+        FeedFormat::Rss => (
+            feed::render_rss(&feed),
+            "application/rss+xml; charset=utf-8",
+        ),
+        FeedFormat::Atom => (
+            feed::render_atom(&feed),
+            "application/atom+xml; charset=utf-8",
+        ),
+        FeedFormat::Json => (
+            feed::render_json(&feed),
+            "application/feed+json; charset=utf-8",
+        ),
+    };
+
+    Ok(ok_response(etag, updated, content_type, body))
+}
+
+async fn build_feed_item(
+    site: &SiteConfig,
+    article: &Article,
+    author: &User,
+    full_content: bool,
+) -> Result<FeedItem, Error> {
+    let content = if full_content && !article.article_uri.is_empty() {
+        let document = s3::get_object(&article.article_uri).await?;
+        // Feed readers expect HTML, Markdown bodies are rendered first
+        let content = if html::is_html(&document) {
+            html::body_of(&document).to_string()
+        } else {
+            markdown::render_article(&document)
+        };
+        Some(content.trim().to_string())
+    } else {
+        None
+    };
+
+    let image = match &article.cover_uri {
+        Some(cover) => Some(s3::get_object_url(cover)?),
+        None => None,
+    };
+    let author_avatar = match &author.profile_pic_uri {
+        Some(avatar) => Some(s3::get_object_url(avatar)?),
+        None => None,
+    };
+    let attachments = article
+        .images_uri_list
+        .iter()
+        .flatten()
+        .map(|uri| s3::get_object_url(uri))
+        .collect::<Result<Vec<String>, Error>>()?;
+
+    Ok(FeedItem {
+        id: format!("{}/api/articles/{}", site.url, article.id.id),
+        url: site.article_url(&author.username, &article.slug),
+        title: article.title.clone(),
+        author: format!("{} {}", author.first_name, author.last_name),
+        author_avatar,
+        tags: article.tags.clone().unwrap_or_default(),
+        summary: article
+            .excerpt
+            .clone()
+            .or_else(|| article.description.clone()),
+        content,
+        image,
+        attachments,
+        published: article.created_at,
+        updated: article.updated_at.unwrap_or(article.created_at),
+    })
+}
+
+// Answers with `304 Not Modified` when the client already has this exact version
+pub fn conditional_response(
+    headers: &HeaderMap,
+    body: String,
+    content_type: &str,
+    last_modified: DateTime<Utc>,
+) -> Response {
+    let etag = etag_of(&body);
+    if is_not_modified(headers, &etag, last_modified) {
+        return not_modified_response(etag, last_modified);
+    }
+
+    ok_response(etag, last_modified, content_type, body)
+}
+
+fn etag_of(version: &str) -> String {
+    format!(
+        "\"{}\"",
+        sha256::digest(version)
+            .get(0..32)
+            .expect("Unreachable, SHA-256 should provide more than 32 chracter")
+    )
+}
+
+fn http_date(date: DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn is_not_modified(headers: &HeaderMap, etag: &str, last_modified: DateTime<Utc>) -> bool {
+    match headers.get(header::IF_NONE_MATCH) {
+        Some(value) => value.to_str().is_ok_and(|value| {
+            value
+                .split(',')
+                .any(|tag| tag.trim() == etag || tag.trim() == "*")
+        }),
+        None => headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .is_some_and(|since| last_modified.timestamp() <= since.timestamp()),
+    }
+}
+
+fn not_modified_response(etag: String, last_modified: DateTime<Utc>) -> Response {
+    (
+        StatusCode::NOT_MODIFIED,
+        [
+            (header::ETAG, etag),
+            (header::LAST_MODIFIED, http_date(last_modified)),
+        ],
+    )
+        .into_response()
+}
+
+fn ok_response(
+    etag: String,
+    last_modified: DateTime<Utc>,
+    content_type: &str,
+    body: String,
+) -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::ETAG, etag),
+            (header::LAST_MODIFIED, http_date(last_modified)),
+        ],
+        body,
+    )
+        .into_response()
+}
@@ -1,12 +1,15 @@
-use crate::database::{article::ARTICLE_TBL_NAME, comment::COMMENT_TBL_NAME, Database};
+use crate::database::{
+    article::ARTICLE_TBL_NAME, comment::COMMENT_TBL_NAME, user::USER_TBL_NAME, Database,
+};
 use crate::errors::Error;
+use crate::models::like::LikeQuery;
 use crate::server::context::Context;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use serde_json::json;
@@ -19,6 +22,9 @@ pub fn routes(database: Arc<Database>) -> Router {
         .route("/like/article/:article_id", post(like_article))
         .route("/unlike/comment/:comment_id", post(unlike_comment))
         .route("/unlike/article/:article_id", post(unlike_article))
+        .route("/articles/:article_id/likes", get(list_article_likers))
+        .route("/comments/:comment_id/likes", get(list_comment_likers))
+        .route("/users/:user_id/likes", get(list_likes_for_user))
         .with_state(database)
 }
 
@@ -107,3 +113,74 @@ async fn unlike_article(
 
     Ok(res)
 }
+
+async fn list_article_likers(
+    State(database): State<Arc<Database>>,
+    Path(article_id): Path<String>,
+    Query(query): Query<LikeQuery>,
+) -> Result<Response, Error> {
+    let article_id = Thing::from((ARTICLE_TBL_NAME, article_id.as_str()));
+    list_likers(database, article_id, query).await
+}
+
+async fn list_comment_likers(
+    State(database): State<Arc<Database>>,
+    Path(comment_id): Path<String>,
+    Query(query): Query<LikeQuery>,
+) -> Result<Response, Error> {
+    let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
+    list_likers(database, comment_id, query).await
+}
+
+async fn list_likers(
+    database: Arc<Database>,
+    target: Thing,
+    query: LikeQuery,
+) -> Result<Response, Error> {
+    let (total, users) = database.list_likers(&target, &query).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully list likes of `{}`", target)
+        },
+        "total": total,
+        "users": users
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn list_likes_for_user(
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<LikeQuery>,
+) -> Result<Response, Error> {
+    let user_id = Thing::from((USER_TBL_NAME, user_id.as_str()));
+    if database.get_user_with_id(&user_id).await?.deleted {
+        return Err(Error::DBRecordDidNotExist(user_id.to_string()));
+    }
+    match query.kind.as_deref() {
+        None | Some(ARTICLE_TBL_NAME) | Some(COMMENT_TBL_NAME) => {}
+        Some(kind) => {
+            return Err(Error::ServerBadRequest(format!(
+                "Unknown type: `{}`, expected `article` or `comment`",
+                kind
+            )))
+        }
+    }
+    let (total, likes) = database.list_likes_for_user(&user_id, &query).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully list likes of user `{}`", user_id)
+        },
+        "total": total,
+        "likes": likes
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
@@ -1,8 +1,16 @@
 pub mod app;
 pub mod article;
 pub mod comment;
+pub mod feed;
 pub mod healthz;
 pub mod like;
 pub mod login;
 pub mod logout;
+pub mod notification;
+pub mod reaction;
+pub mod report;
+pub mod search;
+pub mod series;
+pub mod sitemap;
+pub mod tag;
 pub mod user;
@@ -0,0 +1,77 @@
+use crate::database::{notification::NOTIFICATION_TBL_NAME, user::USER_TBL_NAME, Database};
+use crate::errors::Error;
+use crate::models::notification::NotificationQuery;
+use crate::server::context::Context;
+use crate::utils;
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+
+pub fn for_user_routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/notifications", get(list_notifications))
+        .route("/notifications/read", post(mark_notifications_read))
+        .with_state(database)
+}
+
+async fn list_notifications(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Response, Error> {
+    let user_id = Thing::from((USER_TBL_NAME, user_id.as_str()));
+    context.check_permissions(Some(user_id.clone()), false)?;
+
+    let (total, unread, notifications) = database.list_notifications(&user_id, &query).await?;
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get notifications.",
+        },
+        "total": total,
+        "unread": unread,
+        "notifications": notifications
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+// Marks everything as read when `notifications` is empty
+async fn mark_notifications_read(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    let user_id = Thing::from((USER_TBL_NAME, user_id.as_str()));
+    context.check_permissions(Some(user_id.clone()), false)?;
+
+    let notification_ids: Vec<Thing> = utils::multipart::parse_id_list(payload, "notifications")
+        .await?
+        .iter()
+        .map(|id| Thing::from((NOTIFICATION_TBL_NAME, id.as_str())))
+        .collect();
+    database
+        .mark_notifications_read(&user_id, &notification_ids)
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully mark notifications as read.",
+        },
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
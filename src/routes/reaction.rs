@@ -0,0 +1,121 @@
+use crate::database::{article::ARTICLE_TBL_NAME, comment::COMMENT_TBL_NAME, Database};
+use crate::errors::Error;
+use crate::server::{config::SiteConfig, context::Context};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+
+pub fn routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/reactions", get(list_reactions))
+        .route(
+            "/reactions/comment/:comment_id/:reaction",
+            post(react_to_comment).delete(remove_comment_reaction),
+        )
+        .route(
+            "/reactions/article/:article_id/:reaction",
+            post(react_to_article).delete(remove_article_reaction),
+        )
+        .with_state(database)
+}
+
+// The reactions users can pick from
+async fn list_reactions() -> Result<Response, Error> {
+    let reactions = SiteConfig::parse_from_env_file()?.reactions;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get reactions"
+        },
+        "reactions": reactions
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn react_to_comment(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((comment_id, reaction)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
+    add_reaction(context, database, comment_id, reaction).await
+}
+
+async fn react_to_article(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((article_id, reaction)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let article_id = Thing::from((ARTICLE_TBL_NAME, article_id.as_str()));
+    add_reaction(context, database, article_id, reaction).await
+}
+
+async fn remove_comment_reaction(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((comment_id, reaction)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let comment_id = Thing::from((COMMENT_TBL_NAME, comment_id.as_str()));
+    remove_reaction(context, database, comment_id, reaction).await
+}
+
+async fn remove_article_reaction(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((article_id, reaction)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let article_id = Thing::from((ARTICLE_TBL_NAME, article_id.as_str()));
+    remove_reaction(context, database, article_id, reaction).await
+}
+
+async fn add_reaction(
+    context: Context,
+    database: Arc<Database>,
+    target: Thing,
+    reaction: String,
+) -> Result<Response, Error> {
+    let reaction_id = database.add_reaction(&context, &target, &reaction).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully add reaction"
+        },
+        "reaction_id": reaction_id
+    }));
+    let res = (StatusCode::CREATED, body).into_response();
+
+    Ok(res)
+}
+
+async fn remove_reaction(
+    context: Context,
+    database: Arc<Database>,
+    target: Thing,
+    reaction: String,
+) -> Result<Response, Error> {
+    database
+        .remove_reaction(&context, &target, &reaction)
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully remove reaction"
+        },
+    }));
+    let res = (StatusCode::ACCEPTED, body).into_response();
+
+    Ok(res)
+}
@@ -0,0 +1,167 @@
+use crate::database::{report::REPORT_TBL_NAME, user::USER_TBL_NAME, Database};
+use crate::errors::Error;
+use crate::models::report::ReportQuery;
+use crate::server::context::Context;
+use crate::utils;
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+
+pub fn routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/reports", get(list_reports).post(create_report))
+        .route("/reports/:report_id", get(get_report))
+        .route("/reports/:report_id/assign", post(assign_report))
+        .route("/reports/:report_id/resolve", post(resolve_report))
+        .with_state(database)
+}
+
+async fn create_report(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    context.check_permissions(None, false)?;
+
+    let mut report = utils::multipart::parse_report_for_create(payload, &context).await?;
+    let report = database.create_report(&mut report).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully create report"
+        },
+        "report_id": report.id
+    }));
+    let res = (StatusCode::CREATED, body).into_response();
+
+    Ok(res)
+}
+
+async fn list_reports(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Query(query): Query<ReportQuery>,
+) -> Result<Response, Error> {
+    context.check_permissions(None, true)?;
+
+    let assignee = query
+        .assigned_to
+        .as_deref()
+        .map(|user_id| Thing::from((USER_TBL_NAME, user_id)));
+    let (total, reports) = database.list_reports(&query, assignee.as_ref()).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully list reports"
+        },
+        "total": total,
+        "reports": reports
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn get_report(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(report_id): Path<String>,
+) -> Result<Response, Error> {
+    context.check_permissions(None, true)?;
+
+    let report = database
+        .get_report(&Thing::from((REPORT_TBL_NAME, report_id.as_str())))
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get report"
+        },
+        "report": report
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+// Assigns the report to the admin given in `assignee`, or to the caller when missing
+async fn assign_report(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(report_id): Path<String>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    context.check_permissions(None, true)?;
+
+    let report = database
+        .get_report(&Thing::from((REPORT_TBL_NAME, report_id.as_str())))
+        .await?;
+    let assignee = match utils::multipart::parse_id_list(payload, "assignee")
+        .await?
+        .first()
+    {
+        Some(user_id) => {
+            let user = database
+                .get_user_with_id(&Thing::from((USER_TBL_NAME, user_id.as_str())))
+                .await?;
+            if !user.is_admin || user.deleted {
+                return Err(Error::ServerBadRequest(format!(
+                    "Reports can only be assigned to admins, `{}` is not one",
+                    user.id
+                )));
+            }
+            user.id
+        }
+        None => context.user_id.clone(),
+    };
+    database.assign_report(&report.id, &assignee).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully assign report to `{}`", assignee)
+        },
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn resolve_report(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(report_id): Path<String>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    context.check_permissions(None, true)?;
+
+    let report = database
+        .get_report(&Thing::from((REPORT_TBL_NAME, report_id.as_str())))
+        .await?;
+    let resolution = utils::multipart::parse_report_resolution(payload).await?;
+    let resolved = database
+        .resolve_report(&context, &report, &resolution)
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully resolve {} report(s)", resolved.len())
+        },
+        "report_ids": resolved
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
@@ -0,0 +1,130 @@
+use crate::database::Database;
+use crate::errors::Error;
+use crate::models::search::{SearchQuery, SuggestQuery, SuggestionKind};
+use crate::server::context::Context;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+const SUGGESTION_LENGTH: usize = 5;
+const SUGGESTION_MAX_LENGTH: usize = 20;
+const SUGGESTION_MAX_QUERY_LENGTH: usize = 64;
+
+pub fn routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/search", get(search))
+        .route("/search/suggest", get(suggest))
+        .route("/search/reindex", post(reindex))
+        .with_state(database)
+}
+
+async fn search(
+    State(database): State<Arc<Database>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Response, Error> {
+    if query.q.trim().is_empty() {
+        return Err(Error::ServerBadRequest(String::from(
+            "Search query `q` must not be empty",
+        )));
+    }
+
+    let user_id = match &query.author {
+        Some(username) => Some(database.get_user_with_username(username).await?.id),
+        None => None,
+    };
+    let (total, results) = database.search(&query, user_id.as_ref()).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully search for `{}`", query.q),
+        },
+        "total": total,
+        "page": query.page.unwrap_or(1).max(1),
+        "results": results
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn suggest(
+    State(database): State<Arc<Database>>,
+    Query(query): Query<SuggestQuery>,
+) -> Result<Response, Error> {
+    let q: String = query
+        .q
+        .trim()
+        .chars()
+        .take(SUGGESTION_MAX_QUERY_LENGTH)
+        .collect();
+    if q.is_empty() {
+        return Err(Error::ServerBadRequest(String::from(
+            "Search query `q` must not be empty",
+        )));
+    }
+
+    let kinds = match &query.types {
+        Some(types) => types
+            .split(',')
+            .map(|kind| kind.trim())
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| match kind {
+                "user" => Ok(SuggestionKind::User),
+                "tag" => Ok(SuggestionKind::Tag),
+                "article" => Ok(SuggestionKind::Article),
+                _ => Err(Error::ServerBadRequest(format!(
+                    "Unknown suggestion type: `{}`",
+                    kind
+                ))),
+            })
+            .collect::<Result<Vec<SuggestionKind>, Error>>()?,
+        None => vec![
+            SuggestionKind::User,
+            SuggestionKind::Tag,
+            SuggestionKind::Article,
+        ],
+    };
+    let limit = query
+        .limit
+        .unwrap_or(SUGGESTION_LENGTH)
+        .clamp(1, SUGGESTION_MAX_LENGTH);
+    let suggestions = database.suggest(&q, &kinds, limit).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully get suggestions for `{}`", q),
+        },
+        "suggestions": suggestions
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn reindex(
+    context: Context,
+    State(database): State<Arc<Database>>,
+) -> Result<Response, Error> {
+    context.check_permissions(None, true)?;
+
+    let count = database.reindex_search().await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully indexed {} records", count),
+        },
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
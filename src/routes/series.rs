@@ -0,0 +1,213 @@
+use crate::database::{
+    article::ARTICLE_TBL_NAME, series::SERIES_TBL_NAME, user::USER_TBL_NAME, Database,
+};
+use crate::errors::Error;
+use crate::server::context::Context;
+use crate::utils;
+
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+
+pub fn routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/series/:series_id", get(get_series))
+        .with_state(database)
+}
+
+pub fn for_user_routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/series", get(list_series).post(create_series))
+        .route("/series/:series_id", delete(delete_series))
+        .route("/series/:series_id/articles", put(reorder_series))
+        .route(
+            "/series/:series_id/articles/:article_id",
+            post(add_article_to_series).delete(remove_article_from_series),
+        )
+        .with_state(database)
+}
+
+async fn get_series(
+    context: Option<Context>,
+    State(database): State<Arc<Database>>,
+    Path(series_id): Path<String>,
+) -> Result<Response, Error> {
+    let series = database
+        .get_series_for_reader(
+            &Thing::from((SERIES_TBL_NAME, series_id.as_str())),
+            &context,
+        )
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get series.",
+        },
+        "series": series
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn list_series(
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+) -> Result<Response, Error> {
+    let user_id = Thing::from((USER_TBL_NAME, user_id.as_str()));
+    let series = database.list_series_for_user(&user_id).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully list series for user `{}`", user_id)
+        },
+        "series": series
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn create_series(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(user_id): Path<String>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    let user_id = Thing::from((USER_TBL_NAME, user_id.as_str()));
+    context.check_permissions(Some(user_id), false)?;
+
+    let mut series = utils::multipart::parse_series_for_create(payload, &context).await?;
+    let series_id = database.create_series(&mut series).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully created series.",
+        },
+        "series_id": series_id
+    }));
+    let res = (StatusCode::CREATED, body).into_response();
+
+    Ok(res)
+}
+
+async fn delete_series(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((_, series_id)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let series_id = Thing::from((SERIES_TBL_NAME, series_id.as_str()));
+    let series = database.get_series(&series_id).await?;
+    context.check_permissions(Some(series.user_id), false)?;
+
+    let series = database.delete_series(&series_id).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully delete series.",
+        },
+        "series": series
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn add_article_to_series(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((_, series_id, article_id)): Path<(String, String, String)>,
+) -> Result<Response, Error> {
+    let series = database
+        .get_series(&Thing::from((SERIES_TBL_NAME, series_id.as_str())))
+        .await?;
+    context.check_permissions(Some(series.user_id.clone()), false)?;
+
+    let article = database
+        .get_article_with_id(&Thing::from((ARTICLE_TBL_NAME, article_id.as_str())))
+        .await?;
+    if article.user_id != series.user_id {
+        return Err(Error::ServerPermissionDenied(String::from(
+            "Only articles of the series' author can be added to it",
+        )));
+    }
+    database.add_article_to_series(&series, &article.id).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully add article to series.",
+        },
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn remove_article_from_series(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((_, series_id, article_id)): Path<(String, String, String)>,
+) -> Result<Response, Error> {
+    let series = database
+        .get_series(&Thing::from((SERIES_TBL_NAME, series_id.as_str())))
+        .await?;
+    context.check_permissions(Some(series.user_id.clone()), false)?;
+
+    database
+        .remove_article_from_series(
+            &series,
+            &Thing::from((ARTICLE_TBL_NAME, article_id.as_str())),
+        )
+        .await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully remove article from series.",
+        },
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn reorder_series(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((_, series_id)): Path<(String, String)>,
+    payload: Multipart,
+) -> Result<Response, Error> {
+    let series = database
+        .get_series(&Thing::from((SERIES_TBL_NAME, series_id.as_str())))
+        .await?;
+    context.check_permissions(Some(series.user_id.clone()), false)?;
+
+    let order = utils::multipart::parse_id_list(payload, "articles")
+        .await?
+        .iter()
+        .map(|id| Thing::from((ARTICLE_TBL_NAME, id.as_str())))
+        .collect();
+    database.reorder_series(&series, order).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully reorder series.",
+        },
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
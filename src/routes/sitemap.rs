@@ -0,0 +1,166 @@
+use crate::database::{article::ArticleSource, Database};
+use crate::errors::Error;
+use crate::routes::feed::conditional_response;
+use crate::server::config::SiteConfig;
+use crate::utils::sitemap::{self, SitemapEntry, SITEMAP_LENGTH};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use std::{collections::BTreeMap, sync::Arc};
+
+// Served from the root of the site, crawlers do not look for these under `/api`
+pub fn routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/robots.txt", get(robots))
+        .route("/sitemap.xml", get(get_sitemap))
+        .route("/sitemaps/:page", get(get_sitemap_page))
+        .with_state(database)
+}
+
+async fn robots() -> Result<Response, Error> {
+    let site = SiteConfig::parse_from_env_file()?;
+    let body = sitemap::render_robots(&site.robots_disallow, &format!("{}/sitemap.xml", site.url));
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response())
+}
+
+// Small sites get a single sitemap, bigger ones an index pointing to `/sitemaps/<page>.xml`
+async fn get_sitemap(
+    State(database): State<Arc<Database>>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let site = SiteConfig::parse_from_env_file()?;
+    let entries = collect_entries(&database, &site).await?;
+    if entries.len() <= SITEMAP_LENGTH {
+        let last_modified = last_modified(&entries);
+        return Ok(render(
+            &headers,
+            sitemap::render_urlset(&entries),
+            last_modified,
+        ));
+    }
+
+    let pages: Vec<SitemapEntry> = entries
+        .chunks(SITEMAP_LENGTH)
+        .enumerate()
+        .map(|(index, chunk)| SitemapEntry {
+            loc: format!("{}/sitemaps/{}.xml", site.url, index + 1),
+            lastmod: chunk.iter().filter_map(|entry| entry.lastmod).max(),
+        })
+        .collect();
+    let last_modified = last_modified(&pages);
+
+    Ok(render(
+        &headers,
+        sitemap::render_index(&pages),
+        last_modified,
+    ))
+}
+
+async fn get_sitemap_page(
+    State(database): State<Arc<Database>>,
+    Path(page): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let page: usize = page
+        .trim_end_matches(".xml")
+        .parse()
+        .ok()
+        .filter(|page| *page > 0)
+        .ok_or(Error::DBRecordDidNotExist(page.clone()))?;
+
+    let site = SiteConfig::parse_from_env_file()?;
+    let entries = collect_entries(&database, &site).await?;
+    let chunk = entries
+        .chunks(SITEMAP_LENGTH)
+        .nth(page - 1)
+        .ok_or(Error::DBRecordDidNotExist(format!("sitemaps/{}.xml", page)))?;
+
+    Ok(render(
+        &headers,
+        sitemap::render_urlset(chunk),
+        last_modified(chunk),
+    ))
+}
+
+fn render(headers: &HeaderMap, body: String, last_modified: DateTime<Utc>) -> Response {
+    conditional_response(
+        headers,
+        body,
+        "application/xml; charset=utf-8",
+        last_modified,
+    )
+}
+
+fn last_modified(entries: &[SitemapEntry]) -> DateTime<Utc> {
+    entries
+        .iter()
+        .filter_map(|entry| entry.lastmod)
+        .max()
+        .unwrap_or(Utc.timestamp_opt(0, 0).unwrap())
+}
+
+// Only public, listed articles end up in the sitemap, along with their authors and tags
+async fn collect_entries(
+    database: &Database,
+    site: &SiteConfig,
+) -> Result<Vec<SitemapEntry>, Error> {
+    let articles = database
+        .list_public_articles(&ArticleSource::Site, None)
+        .await?;
+
+    let mut user_ids: Vec<_> = articles
+        .iter()
+        .map(|article| article.user_id.clone())
+        .collect();
+    user_ids.sort_by_key(|id| id.to_string());
+    user_ids.dedup();
+    let usernames: BTreeMap<String, String> = database
+        .list_users_with_ids(&user_ids)
+        .await?
+        .into_iter()
+        .map(|user| (user.id.to_string(), user.username))
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut authors: BTreeMap<String, DateTime<Utc>> = BTreeMap::new();
+    let mut tags: BTreeMap<String, DateTime<Utc>> = BTreeMap::new();
+    for article in &articles {
+        let Some(username) = usernames.get(&article.user_id.to_string()) else {
+            continue;
+        };
+        let updated_at = article.updated_at.unwrap_or(article.created_at);
+        entries.push(SitemapEntry {
+            loc: site.article_url(username, &article.slug),
+            lastmod: Some(updated_at),
+        });
+
+        let author = authors.entry(username.clone()).or_insert(updated_at);
+        *author = (*author).max(updated_at);
+        for tag in article.tags.iter().flatten() {
+            let tag = tags.entry(tag.clone()).or_insert(updated_at);
+            *tag = (*tag).max(updated_at);
+        }
+    }
+
+    entries.extend(
+        authors
+            .into_iter()
+            .map(|(username, updated_at)| SitemapEntry {
+                loc: site.author_url(&username),
+                lastmod: Some(updated_at),
+            }),
+    );
+    entries.extend(tags.into_iter().map(|(slug, updated_at)| SitemapEntry {
+        loc: site.tag_url(&slug),
+        lastmod: Some(updated_at),
+    }));
+
+    Ok(entries)
+}
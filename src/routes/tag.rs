@@ -0,0 +1,123 @@
+use crate::database::Database;
+use crate::errors::Error;
+use crate::models::tag::{TagForMerge, TagForUpdate};
+use crate::server::context::Context;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+pub fn routes(database: Arc<Database>) -> Router {
+    Router::new()
+        .route("/tags", get(list_tags))
+        .route("/tags/:slug", get(get_tag).patch(rename_tag))
+        .route("/tags/:slug/merge", post(merge_tag))
+        .route("/tags/:slug/articles", get(list_articles_for_tag))
+        .with_state(database)
+}
+
+async fn list_tags(State(database): State<Arc<Database>>) -> Result<Response, Error> {
+    let tags = database.list_tags().await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully list tags",
+        },
+        "tags": tags
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn get_tag(
+    State(database): State<Arc<Database>>,
+    Path(slug): Path<String>,
+) -> Result<Response, Error> {
+    let tag = database.get_tag(&slug).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully get tag",
+        },
+        "tag": tag
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn list_articles_for_tag(
+    State(database): State<Arc<Database>>,
+    Path(slug): Path<String>,
+) -> Result<Response, Error> {
+    let tag = database.get_tag(&slug).await?;
+    let articles = database.list_articles_for_tag(&tag.slug).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully list articles for tag `{}`", tag.slug),
+        },
+        "tag": tag,
+        "articles": articles
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn rename_tag(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(slug): Path<String>,
+    payload: Json<TagForUpdate>,
+) -> Result<Response, Error> {
+    context.check_permissions(None, true)?;
+
+    let tag = database.get_tag(&slug).await?;
+    let tag = database.rename_tag(&tag, &payload.name).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully rename tag",
+        },
+        "tag": tag
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
+
+async fn merge_tag(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path(slug): Path<String>,
+    payload: Json<TagForMerge>,
+) -> Result<Response, Error> {
+    context.check_permissions(None, true)?;
+
+    let tag = database.get_tag(&slug).await?;
+    let into = database.get_tag(&payload.into).await?;
+    let tag = database.merge_tag(&tag, &into).await?;
+
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": format!("Successfully merge tag `{}` into `{}`", slug, tag.slug),
+        },
+        "tag": tag
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
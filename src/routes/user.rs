@@ -8,7 +8,7 @@ use axum::{
     extract::{Multipart, Path, State},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
 use serde_json::json;
@@ -22,11 +22,23 @@ pub fn routes(database: Arc<Database>) -> Router {
             "/users/:user_id",
             get(get_user_with_id).delete(delete_user).patch(update_user),
         )
+        .route(
+            "/users/:user_id/blocks/:blocked_id",
+            post(block_user).delete(unblock_user),
+        )
         .with_state(database.clone())
         .nest(
             "/users/:user_id",
             routes::article::for_user_routes(database.clone()),
         )
+        .nest(
+            "/users/:user_id",
+            routes::series::for_user_routes(database.clone()),
+        )
+        .nest(
+            "/users/:user_id",
+            routes::notification::for_user_routes(database.clone()),
+        )
         .nest("/users", routes::comment::for_user_routes(database))
 }
 
@@ -133,3 +145,53 @@ async fn delete_user(
 
     Ok(res)
 }
+
+// Blocked users can not mention the user and can not be mentioned by them
+async fn block_user(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((id, blocked_id)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let id = Thing::from((USER_TBL_NAME, id.as_str()));
+    context.check_permissions(Some(id.clone()), false)?;
+    let blocked_id = Thing::from((USER_TBL_NAME, blocked_id.as_str()));
+    if id == blocked_id {
+        return Err(Error::ServerBadRequest(String::from(
+            "Users can not block themselves",
+        )));
+    }
+
+    database.get_user_with_id(&blocked_id).await?;
+    database.block_user(&id, &blocked_id).await?;
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully block user.",
+        },
+    }));
+    let res = (StatusCode::CREATED, body).into_response();
+
+    Ok(res)
+}
+
+async fn unblock_user(
+    context: Context,
+    State(database): State<Arc<Database>>,
+    Path((id, blocked_id)): Path<(String, String)>,
+) -> Result<Response, Error> {
+    let id = Thing::from((USER_TBL_NAME, id.as_str()));
+    context.check_permissions(Some(id.clone()), false)?;
+
+    database
+        .unblock_user(&id, &Thing::from((USER_TBL_NAME, blocked_id.as_str())))
+        .await?;
+    let body = Json(json!({
+        "result": {
+            "success": true,
+            "message": "Successfully unblock user.",
+        },
+    }));
+    let res = (StatusCode::OK, body).into_response();
+
+    Ok(res)
+}
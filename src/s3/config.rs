@@ -1,5 +1,6 @@
 use crate::errors::Error;
 
+#[allow(dead_code)]
 pub struct S3Config {
     pub ip: String,
     pub bucket_name: String,
@@ -25,3 +25,41 @@ pub async fn get_bucket() -> Result<Bucket, Error> {
 
     Ok(bucket)
 }
+
+pub async fn delete_objects(paths: &[String]) -> Result<(), Error> {
+    let bucket = get_bucket().await?;
+    for path in paths {
+        log::info!("Deleting file: `{}` from s3.", path);
+        bucket
+            .delete_object(path)
+            .await
+            .map_err(|err| Error::MinioCouldNotDeleteObject(path.clone(), err.to_string()))?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_object(path: &str) -> Result<String, Error> {
+    let response = get_bucket()
+        .await?
+        .get_object(path)
+        .await
+        .map_err(|err| Error::MinioCouldNotGetObject(path.to_string(), err.to_string()))?;
+    let content = response
+        .as_str()
+        .map_err(|err| Error::MinioCouldNotGetObject(path.to_string(), err.to_string()))?
+        .to_string();
+
+    Ok(content)
+}
+
+// Objects are served straight from the bucket, which is expected to allow anonymous reads
+pub fn get_object_url(path: &str) -> Result<String, Error> {
+    let config = S3Config::parse_from_env_file()?;
+    let scheme = if config.https { "https" } else { "http" };
+
+    Ok(format!(
+        "{}://{}:{}/{}/{}",
+        scheme, config.ip, config.api_port, config.bucket_name, path
+    ))
+}
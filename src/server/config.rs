@@ -1,7 +1,15 @@
 use crate::errors::Error;
+use crate::models::comment::ModerationMode;
 
 use std::net::{SocketAddr, ToSocketAddrs};
 
+// Used when `SPAM_THRESHOLD` is not set
+const DEFAULT_SPAM_THRESHOLD: f64 = 0.9;
+// Used when `REPORT_HIDE_THRESHOLD` is not set
+const DEFAULT_REPORT_THRESHOLD: usize = 3;
+// Used when `REACTIONS` is not set
+const DEFAULT_REACTIONS: &str = "👍,❤️,😂,🎉,🤔";
+
 pub struct ServerConfig {
     pub address: SocketAddr,
 }
@@ -29,3 +37,85 @@ impl ServerConfig {
         })
     }
 }
+
+// Site settings are read by request handlers, so a missing one is an error rather than a panic
+fn required_var(name: &str) -> Result<String, Error> {
+    std::env::var(name).map_err(|_| Error::ParseEnvFailedMissingVar(name.to_string()))
+}
+
+pub struct SiteConfig {
+    pub name: String,
+    pub url: String,
+    // Paths listed as `Disallow` in robots.txt
+    pub robots_disallow: Vec<String>,
+    // Used by articles without their own moderation mode
+    pub comment_moderation: ModerationMode,
+    // Comments containing one of these are held for moderation
+    pub spam_blocklist: Vec<String>,
+    // Comments scoring at least this much are held for moderation
+    pub spam_threshold: f64,
+    // Comments and articles reported by this many users are hidden until a moderator looks at them
+    pub report_threshold: usize,
+    // Emoji users can react with to articles and comments
+    pub reactions: Vec<String>,
+}
+
+impl SiteConfig {
+    pub fn parse_from_env_file() -> Result<Self, Error> {
+        Ok(SiteConfig {
+            name: required_var("SITE_NAME")?,
+            url: required_var("SITE_URL")?.trim_end_matches('/').to_string(),
+            // Nothing is disallowed when unset
+            robots_disallow: std::env::var("ROBOTS_DISALLOW")
+                .unwrap_or_default()
+                .split(',')
+                .map(|path| path.trim().to_string())
+                .filter(|path| !path.is_empty())
+                .collect(),
+            comment_moderation: match std::env::var("COMMENT_MODERATION") {
+                Ok(mode) => ModerationMode::from_str(&mode).ok_or_else(|| {
+                    Error::ParseEnvFailedWrongFormat(String::from(
+                        "COMMENT_MODERATION must be one of: open, first_time, always, closed",
+                    ))
+                })?,
+                Err(_) => ModerationMode::Open,
+            },
+            spam_blocklist: std::env::var("SPAM_BLOCKLIST")
+                .unwrap_or_default()
+                .split(',')
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+            spam_threshold: match std::env::var("SPAM_THRESHOLD") {
+                Ok(threshold) => threshold
+                    .parse::<f64>()
+                    .map_err(|error| Error::ParseEnvFailedWrongFormat(error.to_string()))?,
+                Err(_) => DEFAULT_SPAM_THRESHOLD,
+            },
+            report_threshold: match std::env::var("REPORT_HIDE_THRESHOLD") {
+                Ok(threshold) => threshold
+                    .parse::<usize>()
+                    .map_err(|error| Error::ParseEnvFailedWrongFormat(error.to_string()))?,
+                Err(_) => DEFAULT_REPORT_THRESHOLD,
+            },
+            reactions: std::env::var("REACTIONS")
+                .unwrap_or_else(|_| DEFAULT_REACTIONS.to_string())
+                .split(',')
+                .map(|reaction| reaction.trim().to_string())
+                .filter(|reaction| !reaction.is_empty())
+                .collect(),
+        })
+    }
+
+    pub fn article_url(&self, username: &str, slug: &str) -> String {
+        format!("{}/api/users/{}/articles/{}", self.url, username, slug)
+    }
+
+    pub fn author_url(&self, username: &str) -> String {
+        format!("{}/api/u/{}", self.url, username)
+    }
+
+    pub fn tag_url(&self, slug: &str) -> String {
+        format!("{}/api/tags/{}/articles", self.url, slug)
+    }
+}
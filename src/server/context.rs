@@ -50,3 +50,41 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(user_id: &str, user_role: Role) -> Context {
+        Context {
+            user_id: Thing::from(("user", user_id)),
+            user_role,
+        }
+    }
+
+    #[test]
+    fn check_permissions_refuses_other_owners() {
+        let author = Thing::from(("user", "author"));
+
+        assert!(context("author", Role::User)
+            .check_permissions(Some(author.clone()), false)
+            .is_ok());
+        assert!(matches!(
+            context("reader", Role::User).check_permissions(Some(author.clone()), false),
+            Err(Error::ServerPermissionDenied(_))
+        ));
+        assert!(context("admin", Role::Admin)
+            .check_permissions(Some(author), false)
+            .is_ok());
+    }
+
+    #[test]
+    fn check_permissions_refuses_users_on_admin_only() {
+        assert!(context("reader", Role::User)
+            .check_permissions(None, true)
+            .is_err());
+        assert!(context("admin", Role::Admin)
+            .check_permissions(None, true)
+            .is_ok());
+    }
+}
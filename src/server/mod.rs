@@ -4,7 +4,7 @@ pub mod context;
 use crate::database::Database;
 use crate::errors::Error;
 use crate::routes;
-use crate::server::config::ServerConfig;
+use crate::server::config::{ServerConfig, SiteConfig};
 
 use axum::Router;
 
@@ -18,6 +18,8 @@ async fn get_all_routes() -> Result<Router, Error> {
 
 pub async fn start() -> Result<(), Error> {
     let config = ServerConfig::parse_from_env_file()?;
+    // Handlers parse the site config on demand, checking it here fails early instead
+    SiteConfig::parse_from_env_file()?;
 
     log::info!("Server listening on http://{:?}", config.address);
     axum::Server::bind(&config.address)
@@ -0,0 +1,61 @@
+// Small in-process cache with a time to live, shared between requests through `Database`
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub struct Cache<V> {
+    entries: Mutex<HashMap<String, (Instant, V)>>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl<V: Clone> Cache<V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Cache {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+            capacity,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<V> {
+        let entries = self
+            .entries
+            .lock()
+            .expect("Cache lock should not be poisoned");
+        entries
+            .get(key)
+            .filter(|(inserted_at, _)| inserted_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    // Expired entries go first when the cache is full, then the oldest one
+    pub fn insert(&self, key: String, value: V) {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("Cache lock should not be poisoned");
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            entries.retain(|_, (inserted_at, _)| inserted_at.elapsed() < self.ttl);
+            if entries.len() >= self.capacity {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, (inserted_at, _))| *inserted_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(key, (Instant::now(), value));
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .expect("Cache lock should not be poisoned")
+            .clear();
+    }
+}
@@ -0,0 +1,251 @@
+use crate::utils::{html, image::ImageType};
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+pub const FEED_LENGTH: usize = 50;
+
+#[derive(Debug)]
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    pub link: String,
+    pub feed_url: String,
+    pub updated: DateTime<Utc>,
+    pub items: Vec<FeedItem>,
+}
+
+#[derive(Debug)]
+pub struct FeedItem {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub author: String,
+    pub author_avatar: Option<String>,
+    pub tags: Vec<String>,
+    pub summary: Option<String>,
+    pub content: Option<String>,
+    pub image: Option<String>,
+    pub attachments: Vec<String>,
+    pub published: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+}
+
+pub fn render_rss(feed: &Feed) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+    xml.push_str("<channel>\n");
+    push_element(&mut xml, "title", &feed.title);
+    push_element(&mut xml, "link", &feed.link);
+    push_element(&mut xml, "description", &feed.description);
+    push_element(&mut xml, "lastBuildDate", &feed.updated.to_rfc2822());
+    xml.push_str(&format!(
+        "<atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        html::escape(&feed.feed_url)
+    ));
+
+    for item in &feed.items {
+        xml.push_str("<item>\n");
+        push_element(&mut xml, "title", &item.title);
+        push_element(&mut xml, "link", &item.url);
+        xml.push_str(&format!(
+            "<guid isPermaLink=\"false\">{}</guid>\n",
+            html::escape(&item.id)
+        ));
+        push_element(&mut xml, "dc:creator", &item.author);
+        push_element(&mut xml, "pubDate", &item.published.to_rfc2822());
+        for tag in &item.tags {
+            push_element(&mut xml, "category", tag);
+        }
+        if let Some(summary) = &item.summary {
+            push_element(&mut xml, "description", summary);
+        }
+        if let Some(content) = &item.content {
+            push_element(&mut xml, "content:encoded", content);
+        }
+        xml.push_str("</item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+pub fn render_atom(feed: &Feed) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    push_element(&mut xml, "id", &feed.feed_url);
+    push_element(&mut xml, "title", &feed.title);
+    push_element(&mut xml, "subtitle", &feed.description);
+    push_element(&mut xml, "updated", &feed.updated.to_rfc3339());
+    xml.push_str(&format!(
+        "<link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n",
+        html::escape(&feed.feed_url)
+    ));
+    xml.push_str(&format!(
+        "<link href=\"{}\" rel=\"alternate\"/>\n",
+        html::escape(&feed.link)
+    ));
+
+    for item in &feed.items {
+        xml.push_str("<entry>\n");
+        push_element(&mut xml, "id", &item.id);
+        push_element(&mut xml, "title", &item.title);
+        xml.push_str(&format!(
+            "<link href=\"{}\" rel=\"alternate\"/>\n",
+            html::escape(&item.url)
+        ));
+        xml.push_str("<author>");
+        push_element(&mut xml, "name", &item.author);
+        xml.push_str("</author>\n");
+        push_element(&mut xml, "published", &item.published.to_rfc3339());
+        push_element(&mut xml, "updated", &item.updated.to_rfc3339());
+        for tag in &item.tags {
+            xml.push_str(&format!("<category term=\"{}\"/>\n", html::escape(tag)));
+        }
+        if let Some(summary) = &item.summary {
+            push_element(&mut xml, "summary", summary);
+        }
+        if let Some(content) = &item.content {
+            xml.push_str(&format!(
+                "<content type=\"html\">{}</content>\n",
+                html::escape(content)
+            ));
+        }
+        xml.push_str("</entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+pub fn render_json(feed: &Feed) -> String {
+    let items: Vec<serde_json::Value> = feed
+        .items
+        .iter()
+        .map(|item| {
+            let attachments: Vec<serde_json::Value> = item
+                .attachments
+                .iter()
+                .map(|url| {
+                    let extension = url.rsplit('.').next().unwrap_or_default();
+                    json!({
+                        "url": url,
+                        "mime_type": ImageType::from_extension(extension).mime_type(),
+                    })
+                })
+                .collect();
+
+            let mut value = json!({
+                "id": item.id,
+                "url": item.url,
+                "title": item.title,
+                "content_html": item.content.clone().unwrap_or_default(),
+                "date_published": item.published.to_rfc3339(),
+                "date_modified": item.updated.to_rfc3339(),
+                "authors": [{ "name": item.author }],
+                "tags": item.tags,
+            });
+            if let Some(summary) = &item.summary {
+                value["summary"] = json!(summary);
+            }
+            if let Some(image) = &item.image {
+                value["image"] = json!(image);
+            }
+            if let Some(avatar) = &item.author_avatar {
+                value["authors"][0]["avatar"] = json!(avatar);
+            }
+            if !attachments.is_empty() {
+                value["attachments"] = json!(attachments);
+            }
+
+            value
+        })
+        .collect();
+
+    json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": feed.title,
+        "home_page_url": feed.link,
+        "feed_url": feed.feed_url,
+        "description": feed.description,
+        "items": items,
+    })
+    .to_string()
+}
+
+fn push_element(xml: &mut String, name: &str, text: &str) {
+    xml.push_str(&format!("<{}>{}</{}>\n", name, html::escape(text), name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn feed() -> Feed {
+        let date = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        Feed {
+            title: String::from("Tom & Jerry's <blog>"),
+            description: String::from("Cats \"and\" mice"),
+            link: String::from("https://example.com"),
+            feed_url: String::from("https://example.com/api/feeds/articles.rss?a=1&b=2"),
+            updated: date,
+            items: vec![FeedItem {
+                id: String::from("https://example.com/api/articles/1"),
+                url: String::from(
+                    "https://example.com/api/users/tom/articles/hello?ref=feed&x=<y>",
+                ),
+                title: String::from("1 < 2 & 3 > 2"),
+                author: String::from("Tom <tom@example.com>"),
+                author_avatar: None,
+                tags: vec![String::from("c&c")],
+                summary: Some(String::from("<b>bold</b> summary")),
+                content: Some(String::from("<p>Hello <script>alert(1)</script></p>")),
+                image: None,
+                attachments: Vec::new(),
+                published: date,
+                updated: date,
+            }],
+        }
+    }
+
+    #[test]
+    fn rss_escapes_text_and_html() {
+        let xml = render_rss(&feed());
+
+        assert!(xml.contains("<title>Tom &amp; Jerry&#39;s &lt;blog&gt;</title>"));
+        assert!(xml.contains("<title>1 &lt; 2 &amp; 3 &gt; 2</title>"));
+        assert!(xml.contains("<description>Cats &quot;and&quot; mice</description>"));
+        assert!(xml
+            .contains("<link>https://example.com/api/users/tom/articles/hello?ref=feed&amp;x=&lt;y&gt;</link>"));
+        assert!(xml.contains("<category>c&amp;c</category>"));
+        assert!(xml.contains(
+            "<content:encoded>&lt;p&gt;Hello &lt;script&gt;alert(1)&lt;/script&gt;&lt;/p&gt;</content:encoded>"
+        ));
+        assert!(xml.contains("href=\"https://example.com/api/feeds/articles.rss?a=1&amp;b=2\""));
+        assert!(!xml.contains("<script>"));
+    }
+
+    #[test]
+    fn atom_escapes_attributes_and_content() {
+        let xml = render_atom(&feed());
+
+        assert!(xml.contains("<link href=\"https://example.com/api/users/tom/articles/hello?ref=feed&amp;x=&lt;y&gt;\" rel=\"alternate\"/>"));
+        assert!(xml.contains("<name>Tom &lt;tom@example.com&gt;</name>"));
+        assert!(xml.contains("<category term=\"c&amp;c\"/>"));
+        assert!(xml.contains("<content type=\"html\">&lt;p&gt;Hello"));
+        assert!(!xml.contains("<script>"));
+    }
+
+    #[test]
+    fn json_keeps_html_as_a_string() {
+        let json: serde_json::Value = serde_json::from_str(&render_json(&feed())).unwrap();
+
+        assert_eq!(json["title"], "Tom & Jerry's <blog>");
+        assert_eq!(
+            json["items"][0]["content_html"],
+            "<p>Hello <script>alert(1)</script></p>"
+        );
+        assert_eq!(json["items"][0]["tags"][0], "c&c");
+    }
+}
@@ -0,0 +1,297 @@
+// Small helpers to pull plain text out of article bodies, which can either be HTML or Markdown
+
+pub fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(character),
+        }
+    }
+
+    result
+}
+
+pub fn unescape(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// Returns what is inside `<body>`, or the whole document if it has none
+pub fn body_of(document: &str) -> &str {
+    let lowercase = document.to_ascii_lowercase();
+    let Some(start) = lowercase.find("<body") else {
+        return document;
+    };
+    let Some(open_end) = lowercase[start..].find('>') else {
+        return document;
+    };
+    let start = start + open_end + 1;
+    let end = lowercase[start..]
+        .find("</body>")
+        .map(|end| start + end)
+        .unwrap_or(document.len());
+
+    &document[start..end]
+}
+
+pub fn is_html(content: &str) -> bool {
+    content.trim_start().starts_with('<')
+}
+
+// Drops every tag as well as the content of `<head>`, `<script>` and `<style>`
+pub fn strip_tags(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[1..end].trim().to_ascii_lowercase();
+        rest = &rest[end + 1..];
+
+        let name: String = tag
+            .chars()
+            .take_while(|character| character.is_alphanumeric())
+            .collect();
+        if ["head", "script", "style", "title"].contains(&name.as_str()) {
+            let closing = format!("</{}", name);
+            match rest.to_ascii_lowercase().find(&closing) {
+                Some(position) => rest = &rest[position..],
+                None => rest = "",
+            }
+        } else if is_block_tag(&tag) {
+            result.push('\n');
+        }
+    }
+    result.push_str(rest);
+
+    unescape(&result)
+}
+
+fn is_block_tag(tag: &str) -> bool {
+    let name: String = tag
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|character| character.is_alphanumeric())
+        .collect();
+
+    [
+        "p",
+        "div",
+        "br",
+        "li",
+        "ul",
+        "ol",
+        "blockquote",
+        "pre",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "section",
+        "article",
+        "tr",
+    ]
+    .contains(&name.as_str())
+}
+
+// Removes the most common inline Markdown syntax, leaving the text readers would see
+pub fn strip_markdown(markdown: &str) -> String {
+    let mut result = String::with_capacity(markdown.len());
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+
+        let line = line
+            .trim_start_matches('#')
+            .trim_start_matches('>')
+            .trim_start_matches("- ")
+            .trim_start_matches("* ")
+            .trim();
+        let mut text = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find('[') {
+            let Some(middle) = rest[start..].find("](") else {
+                break;
+            };
+            let Some(end) = rest[start + middle..].find(')') else {
+                break;
+            };
+            let prefix = rest[..start].trim_end_matches('!');
+            text.push_str(prefix);
+            text.push_str(&rest[start + 1..start + middle]);
+            rest = &rest[start + middle + end + 1..];
+        }
+        text.push_str(rest);
+
+        result.push_str(&text.replace(['*', '_', '`'], ""));
+        result.push('\n');
+    }
+
+    result
+}
+
+// Whole document as plain text, whether it was written in HTML or Markdown
+pub fn to_plain_text(content: &str) -> String {
+    let text = if is_html(content) {
+        strip_tags(body_of(content))
+    } else {
+        strip_markdown(content)
+    };
+
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+pub fn first_paragraph(content: &str) -> Option<String> {
+    let text = if is_html(content) {
+        let lowercase = content.to_ascii_lowercase();
+        let start = [lowercase.find("<p>"), lowercase.find("<p ")]
+            .into_iter()
+            .flatten()
+            .min();
+        match start {
+            Some(start) => {
+                let end = lowercase[start..]
+                    .find("</p>")
+                    .map(|end| start + end)
+                    .unwrap_or(content.len());
+                strip_tags(&content[start..end])
+            }
+            None => strip_tags(content),
+        }
+    } else {
+        content
+            .split("\n\n")
+            .map(|block| block.trim())
+            .find(|block| {
+                !block.is_empty()
+                    && !block.starts_with('#')
+                    && !block.starts_with("```")
+                    && !block.starts_with("![")
+            })
+            .map(strip_markdown)
+            .unwrap_or_default()
+    };
+
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+pub fn truncate_words(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    for word in text.split_whitespace() {
+        if result.chars().count() + word.chars().count() + 1 > max_length {
+            break;
+        }
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        result.push_str(word);
+    }
+    result.push('…');
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_and_unescape_round_trip() {
+        let text = "<a href=\"x\">Tom & 'Jerry'</a>";
+        let escaped = escape(text);
+
+        assert_eq!(
+            escaped,
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+        assert_eq!(unescape(&escaped), text);
+        assert_eq!(unescape("&amp;lt;"), "&lt;");
+    }
+
+    #[test]
+    fn body_of_returns_the_body_or_the_document() {
+        assert_eq!(
+            body_of("<html><head></head><BODY class=\"a\"><p>Hi</p></BODY></html>"),
+            "<p>Hi</p>"
+        );
+        assert_eq!(body_of("<p>Hi</p>"), "<p>Hi</p>");
+    }
+
+    #[test]
+    fn strip_tags_drops_scripts_and_keeps_blocks_apart() {
+        assert_eq!(
+            strip_tags("<p>One</p><script>alert(1)</script><p>Two &amp; <b>three</b></p>"),
+            "\nOne\n\nTwo & three\n"
+        );
+    }
+
+    #[test]
+    fn strip_markdown_keeps_link_text() {
+        assert_eq!(
+            strip_markdown("# Title\n> **Bold** [link](https://example.com) ![alt](a.png)\n"),
+            "Title\nBold link alt\n"
+        );
+    }
+
+    #[test]
+    fn to_plain_text_handles_both_formats() {
+        assert_eq!(
+            to_plain_text("<html><body><h1>A</h1><p>b  c</p></body></html>"),
+            "A b c"
+        );
+        assert_eq!(to_plain_text("# A\n\n*b*   c"), "A b c");
+    }
+
+    #[test]
+    fn first_paragraph_skips_headings_and_images() {
+        assert_eq!(
+            first_paragraph("# Title\n\n![cover](a.png)\n\nFirst *words* here.\n\nSecond."),
+            Some(String::from("First words here."))
+        );
+        assert_eq!(
+            first_paragraph("<h1>T</h1><p class=\"lead\">First &amp; best</p><p>Next</p>"),
+            Some(String::from("First & best"))
+        );
+        assert_eq!(first_paragraph("# Only a title"), None);
+    }
+
+    #[test]
+    fn truncate_words_stops_at_a_word() {
+        assert_eq!(truncate_words("short", 10), "short");
+        assert_eq!(truncate_words("one two three four", 10), "one two…");
+    }
+}
@@ -19,6 +19,25 @@ impl ImageType {
             _ => ImageType::Unsupported,
         }
     }
+
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_lowercase().as_str() {
+            "png" => ImageType::Png,
+            "jpg" => ImageType::Jpg,
+            "jpeg" => ImageType::Jpeg,
+            "gif" => ImageType::Gif,
+            _ => ImageType::Unsupported,
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageType::Png => "image/png",
+            ImageType::Jpeg | ImageType::Jpg => "image/jpeg",
+            ImageType::Gif => "image/gif",
+            ImageType::Unsupported => "application/octet-stream",
+        }
+    }
 }
 
 impl std::fmt::Display for ImageType {
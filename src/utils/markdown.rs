@@ -0,0 +1,189 @@
+// Markdown allowed in comments: CommonMark without raw HTML, tables or footnotes. Images are
+// turned into links and links must use one of `LINK_SCHEMES`, so the output is safe to embed
+use crate::utils::{html, mention, toc};
+
+use pulldown_cmark::{CowStr, Event, LinkType, Options, Parser, Tag};
+use std::collections::HashMap;
+
+const LINK_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+// `users` maps the usernames that can be mentioned to their profile url
+pub fn render_comment(content: &str, users: &HashMap<String, String>) -> String {
+    // The parser splits text around delimiters, mentions are looked for in the whole of it
+    let mut events: Vec<Event> = Vec::new();
+    for event in Parser::new_ext(content, Options::empty()) {
+        match (event, events.last_mut()) {
+            (Event::Text(text), Some(Event::Text(previous))) => {
+                *previous = CowStr::from(format!("{}{}", previous, text));
+            }
+            (event, _) => events.push(event),
+        }
+    }
+
+    // Whether each open link was kept, so that its end goes the same way
+    let mut links: Vec<bool> = Vec::new();
+    let mut in_code_block = false;
+    let events = events.into_iter().filter_map(|event| match event {
+        Event::Html(_) => None,
+        Event::Start(Tag::Link(kind, url, _) | Tag::Image(kind, url, _)) => {
+            let url = match kind {
+                LinkType::Email => format!("mailto:{}", url),
+                _ => url.to_string(),
+            };
+            // Links can not be nested, an image inside of one is left as its description
+            let is_kept = links.is_empty() && is_safe_url(&url);
+            links.push(is_kept);
+            is_kept.then(|| {
+                Event::Html(CowStr::from(format!(
+                    "<a href=\"{}\" rel=\"nofollow ugc\">",
+                    html::escape(&url)
+                )))
+            })
+        }
+        Event::End(Tag::Link(..) | Tag::Image(..)) => links
+            .pop()
+            .filter(|is_kept| *is_kept)
+            .map(|_| Event::Html(CowStr::from("</a>"))),
+        Event::Start(Tag::CodeBlock(kind)) => {
+            in_code_block = true;
+            Some(Event::Start(Tag::CodeBlock(kind)))
+        }
+        Event::End(Tag::CodeBlock(kind)) => {
+            in_code_block = false;
+            Some(Event::End(Tag::CodeBlock(kind)))
+        }
+        Event::Text(text) if !in_code_block && links.is_empty() => {
+            Some(Event::Html(CowStr::from(mention::link_text(&text, users))))
+        }
+        // Comments keep the line breaks they were written with
+        Event::SoftBreak => Some(Event::HardBreak),
+        event => Some(event),
+    });
+
+    let mut result = String::with_capacity(content.len());
+    pulldown_cmark::html::push_html(&mut result, events);
+
+    result
+}
+
+// Shared with the table of contents, so both find the same headings
+pub fn article_parser(content: &str) -> Parser<'_, '_> {
+    Parser::new_ext(
+        content,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    )
+}
+
+// Articles are written by their authors and get the whole of CommonMark, raw HTML included.
+// Headings get the ids the table of contents links to
+pub fn render_article(content: &str) -> String {
+    let anchors: Vec<String> = toc::markdown_headings(content)
+        .into_iter()
+        .map(|heading| heading.anchor)
+        .collect();
+    let mut anchors = anchors.iter();
+    let events = article_parser(content).map(|event| match event {
+        Event::Start(Tag::Heading(level, None, classes)) => Event::Start(Tag::Heading(
+            level,
+            anchors.next().map(String::as_str),
+            classes,
+        )),
+        event => event,
+    });
+
+    let mut result = String::with_capacity(content.len());
+    pulldown_cmark::html::push_html(&mut result, events);
+
+    result
+}
+
+// Relative urls are fine, absolute ones must use one of `LINK_SCHEMES`
+fn is_safe_url(url: &str) -> bool {
+    let scheme_end = url.find(':');
+    let path_start = url.find(['/', '?', '#']);
+    match (scheme_end, path_start) {
+        (Some(scheme_end), Some(path_start)) if path_start < scheme_end => true,
+        (Some(scheme_end), _) => {
+            LINK_SCHEMES.contains(&url[..scheme_end].to_ascii_lowercase().as_str())
+        }
+        (None, _) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_article_renders_commonmark() {
+        let html = render_article(
+            "# Title\n\nSome *text* with `code`.\n\n| a | b |\n|---|---|\n| 1 | 2 |\n",
+        );
+
+        assert!(html.contains("<h1 id=\"title\">Title</h1>"));
+        assert!(html.contains("<p>Some <em>text</em> with <code>code</code>.</p>"));
+        assert!(html.contains("<table>"));
+    }
+
+    fn render(content: &str) -> String {
+        render_comment(content, &HashMap::new())
+    }
+
+    #[test]
+    fn render_comment_drops_raw_html() {
+        assert_eq!(
+            render("Hi <b>there</b> & <img src=x onerror=alert(1)>bye"),
+            "<p>Hi there &amp; bye</p>\n"
+        );
+        assert_eq!(render("<script>alert('x')</script>"), "");
+    }
+
+    #[test]
+    fn render_comment_drops_unsafe_links() {
+        assert_eq!(
+            render("[click](javascript:alert(1)) [x](JavaScript:void(0)) <javascript:alert(1)>"),
+            "<p>click x javascript:alert(1)</p>\n"
+        );
+        assert_eq!(
+            render("[ok](https://example.com/a_(b)) [relative](/path?a=1:2) <me@example.com>"),
+            "<p><a href=\"https://example.com/a_(b)\" rel=\"nofollow ugc\">ok</a> \
+             <a href=\"/path?a=1:2\" rel=\"nofollow ugc\">relative</a> \
+             <a href=\"mailto:me@example.com\" rel=\"nofollow ugc\">me@example.com</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn render_comment_turns_images_into_links() {
+        assert_eq!(
+            render(
+                "![a *cat*](https://example.com/cat.png) [![dog](dog.png)](https://example.com)"
+            ),
+            "<p><a href=\"https://example.com/cat.png\" rel=\"nofollow ugc\">a <em>cat</em></a> \
+             <a href=\"https://example.com\" rel=\"nofollow ugc\">dog</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn render_comment_keeps_line_breaks() {
+        assert_eq!(render("one\ntwo"), "<p>one<br />\ntwo</p>\n");
+    }
+
+    #[test]
+    fn render_comment_links_known_mentions_outside_of_code_and_links() {
+        let users = HashMap::from([(
+            String::from("alice_b"),
+            String::from("https://example.com/api/u/alice_b"),
+        )]);
+
+        assert_eq!(
+            render_comment(
+                "Hi @alice_b, @bob, `@alice_b` [@alice_b](https://x.org)\n\n```\n@alice_b\n```",
+                &users
+            ),
+            "<p>Hi <a href=\"https://example.com/api/u/alice_b\" class=\"mention\">@alice_b</a>, \
+             @bob, <code>@alice_b</code> \
+             <a href=\"https://x.org\" rel=\"nofollow ugc\">@alice_b</a></p>\n\
+             <pre><code>@alice_b\n</code></pre>\n"
+        );
+    }
+}
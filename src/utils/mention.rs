@@ -0,0 +1,270 @@
+// `@username` mentions in comments and article bodies
+use crate::utils::html;
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+// Mentions in these are left alone, they are either code or already a link
+const SKIPPED_TAGS: [&str; 5] = ["a", "code", "pre", "script", "style"];
+
+fn is_username_character(character: char) -> bool {
+    character.is_alphanumeric() || matches!(character, '_' | '-' | '.')
+}
+
+// Byte ranges of the mentions in `text`, `@` included. An `@` following a word is part of an
+// email address, not a mention
+fn find_mentions(text: &str) -> Vec<Range<usize>> {
+    let mut mentions = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut characters = text.char_indices().peekable();
+    while let Some((start, character)) = characters.next() {
+        if character == '@' && !previous.is_some_and(is_username_character) {
+            let mut end = start + 1;
+            while let Some((position, next)) = characters.peek().copied() {
+                if !is_username_character(next) {
+                    break;
+                }
+                end = position + next.len_utf8();
+                characters.next();
+            }
+            // A trailing dot ends the sentence, not the username
+            while end > start + 1 && text[..end].ends_with('.') {
+                end -= 1;
+            }
+            if end > start + 1 {
+                mentions.push(start..end);
+            }
+            previous = text[..end].chars().last();
+            continue;
+        }
+        previous = Some(character);
+    }
+
+    mentions
+}
+
+// Parts of the content where mentions are looked for
+fn text_ranges(content: &str) -> Vec<Range<usize>> {
+    if html::is_html(content) {
+        html_text_ranges(content)
+    } else {
+        markdown_text_ranges(content)
+    }
+}
+
+// Text between tags, outside of `SKIPPED_TAGS`
+fn html_text_ranges(document: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut skipped = 0usize;
+    let mut position = 0;
+    while let Some(offset) = document[position..].find('<') {
+        let start = position + offset;
+        if skipped == 0 && start > position {
+            ranges.push(position..start);
+        }
+        let end = document[start..]
+            .find('>')
+            .map(|end| start + end + 1)
+            .unwrap_or(document.len());
+
+        let tag = &document[start + 1..end];
+        let is_closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|character| character.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if SKIPPED_TAGS.contains(&name.as_str()) {
+            if is_closing {
+                skipped = skipped.saturating_sub(1);
+            } else if !tag.trim_end_matches('>').ends_with('/') {
+                skipped += 1;
+            }
+        }
+        position = end;
+    }
+    if skipped == 0 && position < document.len() {
+        ranges.push(position..document.len());
+    }
+
+    ranges
+}
+
+// Everything but code blocks, inline code and links
+fn markdown_text_ranges(markdown: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut in_code_block = false;
+    let mut offset = 0;
+    for line in markdown.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let mut start = 0;
+        let mut position = 0;
+        while position < line.len() {
+            let skipped_until = match line.as_bytes()[position] {
+                b'`' => line[position + 1..]
+                    .find('`')
+                    .map(|end| position + 1 + end + 1),
+                b'[' => line[position..].find("](").and_then(|middle| {
+                    line[position + middle..]
+                        .find(')')
+                        .map(|end| position + middle + end + 1)
+                }),
+                _ => None,
+            };
+            match skipped_until {
+                Some(end) => {
+                    if position > start {
+                        ranges.push(line_start + start..line_start + position);
+                    }
+                    start = end;
+                    position = end;
+                }
+                None => position += 1,
+            }
+        }
+        if start < line.len() {
+            ranges.push(line_start + start..line_start + line.len());
+        }
+    }
+
+    ranges
+}
+
+// Usernames mentioned in the content, in order of first appearance
+pub fn extract_mentions(content: &str) -> Vec<String> {
+    let mut usernames: Vec<String> = Vec::new();
+    for range in text_ranges(content) {
+        let text = &content[range];
+        for mention in find_mentions(text) {
+            let username = &text[mention.start + 1..mention.end];
+            if !usernames.iter().any(|element| element == username) {
+                usernames.push(username.to_string());
+            }
+        }
+    }
+
+    usernames
+}
+
+fn html_link(username: &str, url: &str) -> String {
+    format!(
+        "<a href=\"{}\" class=\"mention\">@{}</a>",
+        html::escape(url),
+        html::escape(username)
+    )
+}
+
+// Turns the mentions of known users into links to their profile, written in the same format as
+// the content. `users` maps usernames to profile urls
+pub fn link_mentions(content: &str, users: &HashMap<String, String>) -> String {
+    let is_html = html::is_html(content);
+    let mut result = String::with_capacity(content.len());
+    let mut position = 0;
+    for range in text_ranges(content) {
+        for mention in find_mentions(&content[range.clone()]) {
+            let (start, end) = (range.start + mention.start, range.start + mention.end);
+            let username = &content[start + 1..end];
+            let Some(url) = users.get(username) else {
+                continue;
+            };
+            result.push_str(&content[position..start]);
+            if is_html {
+                result.push_str(&html_link(username, url));
+            } else {
+                result.push_str(&format!("[@{}]({})", username, url));
+            }
+            position = end;
+        }
+    }
+    result.push_str(&content[position..]);
+
+    result
+}
+
+// Escapes plain text and links the mentions of known users in it
+pub fn link_text(text: &str, users: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    for mention in find_mentions(text) {
+        let username = &text[mention.start + 1..mention.end];
+        let Some(url) = users.get(username) else {
+            continue;
+        };
+        result.push_str(&html::escape(&text[position..mention.start]));
+        result.push_str(&html_link(username, url));
+        position = mention.end;
+    }
+    result.push_str(&html::escape(&text[position..]));
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users() -> HashMap<String, String> {
+        HashMap::from([(
+            String::from("alice"),
+            String::from("https://example.com/api/u/alice"),
+        )])
+    }
+
+    #[test]
+    fn extract_mentions_ignores_emails_and_trailing_dots() {
+        assert_eq!(
+            extract_mentions("Thanks @alice. Mail bob@example.com or ping @carol_b, @alice!"),
+            vec![String::from("alice"), String::from("carol_b")]
+        );
+        assert!(extract_mentions("Just an @ sign").is_empty());
+    }
+
+    #[test]
+    fn extract_mentions_skips_markdown_code_and_links() {
+        let markdown =
+            "Ask @alice about `@inline`\n```\n@block\n```\n[@linked](https://x.org) @bob";
+
+        assert_eq!(
+            extract_mentions(markdown),
+            vec![String::from("alice"), String::from("bob")]
+        );
+    }
+
+    #[test]
+    fn extract_mentions_skips_html_code_and_links() {
+        let document =
+            "<p>Hi @alice <code>@inline</code> <a href=\"#\">@linked</a></p><pre>@block</pre>";
+
+        assert_eq!(extract_mentions(document), vec![String::from("alice")]);
+    }
+
+    #[test]
+    fn link_mentions_keeps_the_content_format() {
+        assert_eq!(
+            link_mentions("Hi @alice and @bob.", &users()),
+            "Hi [@alice](https://example.com/api/u/alice) and @bob."
+        );
+        assert_eq!(
+            link_mentions("<p>Hi @alice</p>", &users()),
+            "<p>Hi <a href=\"https://example.com/api/u/alice\" class=\"mention\">@alice</a></p>"
+        );
+    }
+
+    #[test]
+    fn link_text_escapes_the_text() {
+        assert_eq!(
+            link_text("<b>@alice</b>", &users()),
+            "&lt;b&gt;<a href=\"https://example.com/api/u/alice\" class=\"mention\">@alice</a>&lt;/b&gt;"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use crate::errors::Error;
+use crate::models::{article::Article, user::User};
+use crate::s3;
+use crate::server::config::SiteConfig;
+use crate::utils::html;
+
+use serde::Serialize;
+
+// Open Graph uses `property` while Twitter Cards use `name`
+#[derive(Debug, Serialize)]
+pub struct MetaTag {
+    pub attribute: &'static str,
+    pub key: String,
+    pub content: String,
+}
+
+impl MetaTag {
+    fn property(key: &str, content: &str) -> Self {
+        MetaTag {
+            attribute: "property",
+            key: key.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn name(key: &str, content: &str) -> Self {
+        MetaTag {
+            attribute: "name",
+            key: key.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        format!(
+            "<meta {}=\"{}\" content=\"{}\">",
+            self.attribute,
+            html::escape(&self.key),
+            html::escape(&self.content)
+        )
+    }
+}
+
+pub fn social_meta_for_article(
+    article: &Article,
+    author: &User,
+    site: &SiteConfig,
+) -> Result<Vec<MetaTag>, Error> {
+    let url = site.article_url(&author.username, &article.slug);
+    let description = article
+        .description
+        .as_ref()
+        .or(article.excerpt.as_ref())
+        .cloned()
+        .unwrap_or_default();
+    let image = match &article.cover_uri {
+        Some(cover) => Some(s3::get_object_url(cover)?),
+        None => None,
+    };
+
+    let mut tags = vec![
+        MetaTag::property("og:type", "article"),
+        MetaTag::property("og:site_name", &site.name),
+        MetaTag::property("og:title", &article.title),
+        MetaTag::property("og:url", &url),
+        MetaTag::property("og:description", &description),
+        MetaTag::property("article:published_time", &article.created_at.to_rfc3339()),
+        MetaTag::property(
+            "article:author",
+            &format!("{} {}", author.first_name, author.last_name),
+        ),
+    ];
+    if let Some(updated_at) = article.updated_at {
+        tags.push(MetaTag::property(
+            "article:modified_time",
+            &updated_at.to_rfc3339(),
+        ));
+    }
+    for tag in article.tags.iter().flatten() {
+        tags.push(MetaTag::property("article:tag", tag));
+    }
+
+    tags.push(MetaTag::name("twitter:title", &article.title));
+    tags.push(MetaTag::name("twitter:description", &description));
+    match &image {
+        Some(image) => {
+            tags.push(MetaTag::property("og:image", image));
+            tags.push(MetaTag::name("twitter:card", "summary_large_image"));
+            tags.push(MetaTag::name("twitter:image", image));
+        }
+        None => tags.push(MetaTag::name("twitter:card", "summary")),
+    }
+
+    Ok(tags)
+}
@@ -1,5 +1,15 @@
+pub mod cache;
+pub mod feed;
+pub mod html;
 pub mod image;
+pub mod markdown;
+pub mod mention;
+pub mod meta;
 pub mod multipart;
+pub mod search;
+pub mod sitemap;
+pub mod spam;
+pub mod toc;
 
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
@@ -7,12 +17,17 @@ use surrealdb::sql::Thing;
 
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
+#[allow(dead_code)]
 pub enum OpChangesValue {
     Bool(bool),
+    Number(f64),
     Datetime(DateTime<Utc>),
     Id(Thing),
     VecId(Vec<Thing>),
+    VecText(Vec<String>),
     Text(String),
+    // Objects and anything else a patch can hold, so a diff never fails to parse
+    Other(serde_json::Value),
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,3 +37,56 @@ pub struct OpChanges {
     path: String,
     value: Option<OpChangesValue>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use surrealdb::{engine::local::Mem, Surreal};
+
+    #[tokio::test]
+    async fn op_changes_parse_every_patched_type() {
+        let client = Surreal::new::<Mem>(()).await.unwrap();
+        client.use_ns("test").use_db("test").await.unwrap();
+        client
+            .query("CREATE article:one SET title = 'Title', public = false")
+            .await
+            .unwrap();
+
+        let mut response = client
+            .query(
+                "UPDATE article:one SET title = 'New title', public = true, word_count = 12, \
+                 spam_score = <float> 0.5, tags = ['rust', 'web'], updated_at = time::now(), \
+                 toc = [{ level: 2, text: 'Intro', anchor: 'intro' }] RETURN DIFF",
+            )
+            .await
+            .unwrap();
+        let changes: Option<Vec<OpChanges>> = response.take(0).unwrap();
+        let changes = changes.unwrap();
+
+        assert_eq!(changes.len(), 7);
+        let value_of = |path: &str| {
+            changes
+                .iter()
+                .find(|change| change.path == path)
+                .and_then(|change| change.value.as_ref())
+        };
+        assert!(matches!(
+            value_of("/public"),
+            Some(OpChangesValue::Bool(true))
+        ));
+        assert!(matches!(
+            value_of("/word_count"),
+            Some(OpChangesValue::Number(_))
+        ));
+        assert!(matches!(
+            value_of("/spam_score"),
+            Some(OpChangesValue::Number(_))
+        ));
+        assert!(matches!(
+            value_of("/tags"),
+            Some(OpChangesValue::VecText(_))
+        ));
+        assert!(matches!(value_of("/toc"), Some(OpChangesValue::Other(_))));
+    }
+}
@@ -1,16 +1,31 @@
 use crate::errors::Error;
-use crate::models::{article::ArticleForCreate, comment::CommentForCreate, user::UserForCreate};
+use crate::models::{
+    article::{ArticleForCreate, ArticleForUpdate},
+    comment::{CommentForCreate, ModerationMode},
+    report::{ReportAction, ReportForCreate, ReportReason, ReportResolution},
+    series::SeriesForCreate,
+    user::UserForCreate,
+};
 use crate::s3;
 use crate::server::context::Context;
-use crate::utils::image::{Image, ImageType};
+use crate::utils::{
+    image::{Image, ImageType},
+    toc,
+};
 
-use axum::{body::Bytes, extract::Multipart};
+use axum::{
+    body::Bytes,
+    extract::{multipart::Field, Multipart},
+};
+use chrono::{DateTime, Utc};
 use surrealdb::sql::Thing;
 
 pub async fn parse_article_for_update(
     mut payload: Multipart,
     article_uri: &String,
-) -> Result<(), Error> {
+) -> Result<ArticleForUpdate, Error> {
+    let mut article = ArticleForUpdate::default();
+
     while let Some(field) = payload
         .next_field()
         .await
@@ -20,26 +35,89 @@ pub async fn parse_article_for_update(
             let name = field_name.to_string();
             let file_type = field.content_type();
             if name == "file" {
-                if let Some(file_type) = file_type {
-                    if file_type != "text/html" {
-                        return Err(Error::ServerUnsupportedMediaType(file_type.to_string()));
-                    }
+                let content_type = file_type.unwrap_or("text/html").to_string();
+                if content_type != "text/html" && content_type != "text/markdown" {
+                    return Err(Error::ServerUnsupportedMediaType(content_type));
                 }
                 let data = field
                     .bytes()
                     .await
                     .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                let mut content = parse_string_from_u8(&data)?;
+                if content_type == "text/html" {
+                    content = toc::add_heading_anchors(&content).0;
+                }
 
                 s3::get_bucket()
                     .await?
-                    .put_object_with_content_type(article_uri, &data, "text/html")
+                    .put_object_with_content_type(article_uri, content.as_bytes(), &content_type)
                     .await
                     .map_err(|err| Error::MinioCouldNotPutObject(err.to_string()))?;
+                article.content = Some(content);
+            } else if name == "title" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.title = Some(parse_string_from_u8(&data)?);
+            } else if name == "public" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.public = Some(parse_bool_from_u8(&data)?);
+            } else if name == "unlisted" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.unlisted = Some(parse_bool_from_u8(&data)?);
+            } else if name == "moderation" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.moderation = Some(parse_moderation_from_u8(&data)?);
+            } else if name == "comments_locked" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.comments_locked = Some(parse_bool_from_u8(&data)?);
+            } else if name == "comments_closed_at" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.comments_closed_at = Some(parse_optional_datetime_from_u8(&data)?);
+            } else if name == "tags" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article
+                    .tags
+                    .get_or_insert_with(Vec::new)
+                    .extend(parse_list_from_u8(&data)?);
+            } else if name == "excerpt" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.excerpt = Some(parse_string_from_u8(&data)?.trim().to_string());
+            } else if name == "description" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                article.description = Some(parse_string_from_u8(&data)?.trim().to_string());
+            } else if name == "cover" {
+                article.cover = Some(parse_image(field).await?);
             }
         }
     }
 
-    Ok(())
+    Ok(article)
 }
 
 // TODO: Find a better way to parse multipart form to struct
@@ -57,7 +135,7 @@ pub async fn parse_user_for_create(mut payload: Multipart) -> Result<UserForCrea
 
             if name == "avatar" {
                 user.avatar = Some(Image::new());
-                let mut avatar = user
+                let avatar = user
                     .avatar
                     .as_mut()
                     .expect("Unreachable, avatar should be contructed by now");
@@ -107,6 +185,17 @@ pub async fn parse_user_for_create(mut payload: Multipart) -> Result<UserForCrea
                 user.is_admin = parse_string_from_u8(&data)?
                     .parse::<bool>()
                     .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+            } else if name == "comments_auto_close_days" {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+                user.comments_auto_close_days = Some(
+                    parse_string_from_u8(&data)?
+                        .trim()
+                        .parse::<u32>()
+                        .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?,
+                );
             }
         }
     }
@@ -122,6 +211,48 @@ fn parse_string_from_u8(data: &Bytes) -> Result<String, Error> {
     Ok(result)
 }
 
+fn parse_bool_from_u8(data: &Bytes) -> Result<bool, Error> {
+    match parse_string_from_u8(data)?.trim() {
+        "true" | "1" | "on" => Ok(true),
+        "false" | "0" | "off" => Ok(false),
+        value => Err(Error::ServerCouldNotParseForm(format!(
+            "Expected a boolean, got `{}`",
+            value
+        ))),
+    }
+}
+
+// RFC 3339, an empty value means no date
+fn parse_optional_datetime_from_u8(data: &Bytes) -> Result<Option<DateTime<Utc>>, Error> {
+    match parse_string_from_u8(data)?.trim() {
+        "" => Ok(None),
+        value => DateTime::parse_from_rfc3339(value)
+            .map(|datetime| Some(datetime.with_timezone(&Utc)))
+            .map_err(|err| Error::ServerCouldNotParseForm(format!("`{}`: {}", value, err))),
+    }
+}
+
+// `site` or an empty value means the article follows the site setting
+fn parse_moderation_from_u8(data: &Bytes) -> Result<Option<ModerationMode>, Error> {
+    match parse_string_from_u8(data)?.trim() {
+        "" | "site" => Ok(None),
+        value => ModerationMode::from_str(value).map(Some).ok_or_else(|| {
+            Error::ServerCouldNotParseForm(format!("Unknown moderation mode: `{}`", value))
+        }),
+    }
+}
+
+// Lists can either be sent as a comma separated string or as repeated fields
+fn parse_list_from_u8(data: &Bytes) -> Result<Vec<String>, Error> {
+    let result = parse_string_from_u8(data)?
+        .split(',')
+        .map(|element| element.trim().to_string())
+        .filter(|element| !element.is_empty())
+        .collect();
+
+    Ok(result)
+}
+
 pub async fn upload_user_image_to_s3(base_folder: &str, image: &Image) -> Result<String, Error> {
     let file_name = format!(
         "{}/{}.{}",
@@ -160,12 +291,36 @@ pub async fn parse_article_for_create(
     {
         if let Some(field_name) = field.name() {
             let name = field_name.to_string();
+            if name == "cover" {
+                article.cover = Some(parse_image(field).await?);
+                continue;
+            }
+
             let data = field
                 .bytes()
                 .await
                 .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
             if name == "title" {
                 article.title = parse_string_from_u8(&data)?;
+            } else if name == "public" {
+                article.public = parse_bool_from_u8(&data)?;
+            } else if name == "unlisted" {
+                article.unlisted = parse_bool_from_u8(&data)?;
+            } else if name == "moderation" {
+                article.moderation = parse_moderation_from_u8(&data)?;
+            } else if name == "comments_locked" {
+                article.comments_locked = parse_bool_from_u8(&data)?;
+            } else if name == "comments_closed_at" {
+                article.comments_closed_at = parse_optional_datetime_from_u8(&data)?;
+            } else if name == "tags" {
+                article.tags.extend(parse_list_from_u8(&data)?);
+            } else if name == "excerpt" {
+                let excerpt = parse_string_from_u8(&data)?.trim().to_string();
+                article.excerpt_is_custom = !excerpt.is_empty();
+                article.excerpt = Some(excerpt).filter(|excerpt| !excerpt.is_empty());
+            } else if name == "description" {
+                let description = parse_string_from_u8(&data)?.trim().to_string();
+                article.description = Some(description).filter(|element| !element.is_empty());
             }
         }
     }
@@ -235,7 +390,7 @@ pub async fn parse_comment_for_create(
                 comment.content = Some(parse_string_from_u8(&data)?);
             } else if name == "media" {
                 comment.image = Some(Image::new());
-                let mut image = comment
+                let image = comment
                     .image
                     .as_mut()
                     .expect("Unreachable, comment media should be contructed by now");
@@ -259,3 +414,178 @@ pub async fn parse_comment_for_create(
 
     Ok(comment)
 }
+
+pub async fn parse_series_for_create(
+    mut payload: Multipart,
+    context: &Context,
+) -> Result<SeriesForCreate, Error> {
+    let mut series = SeriesForCreate::new();
+    series.user_id = context.user_id.clone();
+
+    while let Some(field) = payload
+        .next_field()
+        .await
+        .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?
+    {
+        if let Some(field_name) = field.name() {
+            let name = field_name.to_string();
+            let data = field
+                .bytes()
+                .await
+                .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+            if name == "title" {
+                series.title = parse_string_from_u8(&data)?;
+            } else if name == "description" {
+                series.description = Some(parse_string_from_u8(&data)?);
+            }
+        }
+    }
+
+    if series.title.is_empty() {
+        return Err(Error::ServerEmptyFormFromUser);
+    }
+
+    Ok(series)
+}
+
+pub async fn parse_report_for_create(
+    mut payload: Multipart,
+    context: &Context,
+) -> Result<ReportForCreate, Error> {
+    let mut report = ReportForCreate::new();
+    report.reporter_id = context.user_id.clone();
+    let mut target_type = String::new();
+    let mut target_id = String::new();
+    let mut reason = String::new();
+
+    while let Some(field) = payload
+        .next_field()
+        .await
+        .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?
+    {
+        if let Some(field_name) = field.name() {
+            let name = field_name.to_string();
+            let data = field
+                .bytes()
+                .await
+                .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+            if name == "target_type" {
+                target_type = parse_string_from_u8(&data)?.trim().to_string();
+            } else if name == "target_id" {
+                target_id = parse_string_from_u8(&data)?.trim().to_string();
+            } else if name == "reason" {
+                reason = parse_string_from_u8(&data)?.trim().to_string();
+            } else if name == "details" {
+                let details = parse_string_from_u8(&data)?.trim().to_string();
+                report.details = Some(details).filter(|details| !details.is_empty());
+            }
+        }
+    }
+
+    if target_type.is_empty() || target_id.is_empty() || reason.is_empty() {
+        return Err(Error::ServerEmptyFormFromUser);
+    }
+    if !["comment", "article", "user"].contains(&target_type.as_str()) {
+        return Err(Error::ServerCouldNotParseForm(format!(
+            "Unknown report target type: `{}`",
+            target_type
+        )));
+    }
+    report.target = Thing::from((target_type.as_str(), target_id.as_str()));
+    report.reason = ReportReason::from_str(&reason).ok_or_else(|| {
+        Error::ServerCouldNotParseForm(format!("Unknown report reason: `{}`", reason))
+    })?;
+
+    Ok(report)
+}
+
+pub async fn parse_report_resolution(mut payload: Multipart) -> Result<ReportResolution, Error> {
+    let mut action = None;
+    let mut note = None;
+
+    while let Some(field) = payload
+        .next_field()
+        .await
+        .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?
+    {
+        if let Some(field_name) = field.name() {
+            let name = field_name.to_string();
+            let data = field
+                .bytes()
+                .await
+                .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+            if name == "action" {
+                let value = parse_string_from_u8(&data)?.trim().to_string();
+                action = Some(ReportAction::from_str(&value).ok_or_else(|| {
+                    Error::ServerCouldNotParseForm(format!("Unknown report action: `{}`", value))
+                })?);
+            } else if name == "note" {
+                let value = parse_string_from_u8(&data)?.trim().to_string();
+                note = Some(value).filter(|note| !note.is_empty());
+            }
+        }
+    }
+
+    Ok(ReportResolution {
+        action: action.ok_or(Error::ServerEmptyFormFromUser)?,
+        note,
+    })
+}
+
+pub async fn parse_id_list(mut payload: Multipart, name: &str) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+    while let Some(field) = payload
+        .next_field()
+        .await
+        .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?
+    {
+        if field.name() == Some(name) {
+            let data = field
+                .bytes()
+                .await
+                .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?;
+            ids.extend(parse_list_from_u8(&data)?);
+        }
+    }
+
+    Ok(ids)
+}
+
+pub async fn parse_images(mut payload: Multipart, name: &str) -> Result<Vec<Image>, Error> {
+    let mut images = Vec::new();
+    while let Some(field) = payload
+        .next_field()
+        .await
+        .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?
+    {
+        if field.name() == Some(name) {
+            images.push(parse_image(field).await?);
+        }
+    }
+
+    if images.is_empty() {
+        return Err(Error::ServerEmptyFormFromUser);
+    }
+
+    Ok(images)
+}
+
+async fn parse_image(field: Field<'_>) -> Result<Image, Error> {
+    let mut image = Image::new();
+    if let Some(file_name) = field.file_name() {
+        image.file_name = file_name.to_string();
+    }
+    if let Some(file_type) = field.content_type() {
+        image.file_type = ImageType::from_str(file_type);
+        if !image.is_supported_image_type() {
+            return Err(Error::ServerUnsupportedMediaType(file_type.to_string()));
+        }
+    }
+    image.data = field
+        .bytes()
+        .await
+        .map_err(|err| Error::ServerCouldNotParseForm(err.to_string()))?
+        .to_vec();
+
+    Ok(image)
+}
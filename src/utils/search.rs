@@ -0,0 +1,265 @@
+// Text analysis used by the search index: tokenizing, stemming, scoring and highlighting
+use crate::utils::html;
+
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::{HashMap, HashSet};
+
+pub const SNIPPET_LENGTH: usize = 200;
+// Matches in the title count more than matches in the body
+pub const TITLE_WEIGHT: usize = 3;
+
+// BM25 parameters
+pub const K1: f64 = 1.2;
+pub const B: f64 = 0.75;
+
+const STOP_WORDS: [&str; 33] = [
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+pub fn stem(word: &str) -> String {
+    Stemmer::create(Algorithm::English)
+        .stem(&word.to_lowercase())
+        .to_string()
+}
+
+// Splits on anything that is not a letter or a digit, drops stop words and stems what is left
+pub fn tokenize(text: &str) -> Vec<String> {
+    let stemmer = Stemmer::create(Algorithm::English);
+    text.split(|character: char| !character.is_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stemmer.stem(&word).to_string())
+        .collect()
+}
+
+pub fn unique_terms(tokens: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tokens
+        .iter()
+        .filter(|token| seen.insert(token.as_str()))
+        .cloned()
+        .collect()
+}
+
+pub struct Corpus {
+    pub total: usize,
+    pub average_length: f64,
+    // Number of documents each query term appears in
+    pub frequencies: HashMap<String, usize>,
+}
+
+impl Corpus {
+    // The rarer the term, the more a match on it is worth
+    pub fn idf(&self, term: &str) -> f64 {
+        let documents = *self.frequencies.get(term).unwrap_or(&0) as f64;
+        let total = self.total as f64;
+
+        ((total - documents + 0.5) / (documents + 0.5) + 1.0).ln()
+    }
+}
+
+// Escapes the text and wraps every word matching the query in `<mark>`, centered on the first match
+pub fn highlight(text: &str, query: &[String], length: usize) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let is_match = |word: &str| {
+        let word: String = word
+            .chars()
+            .filter(|character| character.is_alphanumeric())
+            .collect();
+        !word.is_empty() && query.contains(&stem(&word))
+    };
+
+    let first = words.iter().position(|word| is_match(word)).unwrap_or(0);
+    let mut start = first;
+    let mut size = words[first..]
+        .first()
+        .map(|word| word.chars().count())
+        .unwrap_or(0);
+    while start > 0 && size < length / 3 {
+        start -= 1;
+        size += words[start].chars().count() + 1;
+    }
+
+    let mut snippet = Vec::new();
+    let mut size = 0;
+    for word in &words[start..] {
+        size += word.chars().count() + 1;
+        if size > length && !snippet.is_empty() {
+            break;
+        }
+        if is_match(word) {
+            snippet.push(format!("<mark>{}</mark>", html::escape(word)));
+        } else {
+            snippet.push(html::escape(word));
+        }
+    }
+
+    let mut result = snippet.join(" ");
+    if start > 0 {
+        result.insert_str(0, "… ");
+    }
+    if start + snippet.len() < words.len() {
+        result.push_str(" …");
+    }
+
+    result
+}
+
+// Lower is better: exact match, prefix, prefix of a word, substring and finally fuzzy match
+pub fn match_rank(label: &str, query: &str) -> u8 {
+    let label = label.to_lowercase();
+    if label == query {
+        0
+    } else if label.starts_with(query) {
+        1
+    } else if label
+        .split(|character: char| !character.is_alphanumeric())
+        .any(|word| word.starts_with(query))
+    {
+        2
+    } else if label.contains(query) {
+        3
+    } else {
+        4
+    }
+}
+
+pub fn term_frequencies(tokens: &[String]) -> HashMap<String, f64> {
+    let mut frequencies = HashMap::new();
+    for token in tokens {
+        *frequencies.entry(token.clone()).or_insert(0.0) += 1.0;
+    }
+    let total = tokens.len().max(1) as f64;
+    frequencies.values_mut().for_each(|value| *value /= total);
+
+    frequencies
+}
+
+// Smoothed so that terms present in every document still weigh a little
+pub fn inverse_document_frequencies(documents: &[HashMap<String, f64>]) -> HashMap<String, f64> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for document in documents {
+        for term in document.keys() {
+            *counts.entry(term.clone()).or_insert(0) += 1;
+        }
+    }
+    let total = documents.len() as f64;
+
+    counts
+        .into_iter()
+        .map(|(term, count)| (term, ((1.0 + total) / (1.0 + count as f64)).ln() + 1.0))
+        .collect()
+}
+
+pub fn tf_idf(
+    frequencies: &HashMap<String, f64>,
+    idf: &HashMap<String, f64>,
+) -> HashMap<String, f64> {
+    frequencies
+        .iter()
+        .map(|(term, frequency)| (term.clone(), frequency * idf.get(term).unwrap_or(&1.0)))
+        .collect()
+}
+
+pub fn cosine_similarity(first: &HashMap<String, f64>, second: &HashMap<String, f64>) -> f64 {
+    let dot: f64 = first
+        .iter()
+        .filter_map(|(term, weight)| second.get(term).map(|other| weight * other))
+        .sum();
+    let norm = |vector: &HashMap<String, f64>| {
+        vector
+            .values()
+            .map(|weight| weight * weight)
+            .sum::<f64>()
+            .sqrt()
+    };
+    let norms = norm(first) * norm(second);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+pub fn jaccard_similarity(first: &[String], second: &[String]) -> f64 {
+    let first: HashSet<&String> = first.iter().collect();
+    let second: HashSet<&String> = second.iter().collect();
+    let union = first.union(&second).count();
+    if union == 0 {
+        0.0
+    } else {
+        first.intersection(&second).count() as f64 / union as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn tokenize_lowercases_stems_and_drops_stop_words() {
+        assert_eq!(
+            tokenize("The Runners were RUNNING, and jumping!"),
+            strings(&["runner", "were", "run", "jump"])
+        );
+    }
+
+    #[test]
+    fn tokenize_splits_on_punctuation() {
+        assert_eq!(
+            tokenize("rust-lang's async/await (2023)"),
+            strings(&["rust", "lang", "s", "async", "await", "2023"])
+        );
+        assert!(tokenize("  ... ").is_empty());
+    }
+
+    #[test]
+    fn stem_matches_tokenize() {
+        assert_eq!(stem("Connections"), "connect");
+        assert_eq!(tokenize("Connections"), strings(&["connect"]));
+    }
+
+    #[test]
+    fn unique_terms_keeps_first_occurrence_order() {
+        assert_eq!(
+            unique_terms(&strings(&["web", "rust", "web", "api", "rust"])),
+            strings(&["web", "rust", "api"])
+        );
+    }
+
+    #[test]
+    fn idf_favors_rare_terms() {
+        let corpus = Corpus {
+            total: 100,
+            average_length: 10.0,
+            frequencies: HashMap::from([(String::from("rare"), 1), (String::from("common"), 90)]),
+        };
+
+        assert!(corpus.idf("rare") > corpus.idf("common"));
+        assert!(corpus.idf("common") > 0.0);
+        assert!(corpus.idf("missing") > corpus.idf("rare"));
+    }
+
+    #[test]
+    fn highlight_marks_stemmed_matches_and_escapes() {
+        let snippet = highlight("Running <fast> & jumping", &strings(&["run"]), 200);
+
+        assert_eq!(snippet, "<mark>Running</mark> &lt;fast&gt; &amp; jumping");
+    }
+
+    #[test]
+    fn highlight_centers_on_the_first_match() {
+        let text = "one two three four five six seven eight nine ten match eleven twelve";
+        let snippet = highlight(text, &strings(&["match"]), 20);
+
+        assert!(snippet.starts_with("… "));
+        assert!(snippet.contains("<mark>match</mark>"));
+        assert!(snippet.ends_with(" …"));
+    }
+}
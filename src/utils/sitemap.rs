@@ -0,0 +1,117 @@
+use crate::utils::html;
+
+use chrono::{DateTime, Utc};
+
+// Limit set by the sitemap protocol for a single file
+pub const SITEMAP_LENGTH: usize = 50_000;
+
+#[derive(Debug)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+pub fn render_urlset(entries: &[SitemapEntry]) -> String {
+    render(entries, "urlset", "url")
+}
+
+pub fn render_index(entries: &[SitemapEntry]) -> String {
+    render(entries, "sitemapindex", "sitemap")
+}
+
+fn render(entries: &[SitemapEntry], root: &str, element: &str) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<{} xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        root
+    ));
+    for entry in entries {
+        xml.push_str(&format!(
+            "<{}><loc>{}</loc>",
+            element,
+            html::escape(&entry.loc)
+        ));
+        if let Some(lastmod) = &entry.lastmod {
+            xml.push_str(&format!(
+                "<lastmod>{}</lastmod>",
+                lastmod.format("%Y-%m-%dT%H:%M:%SZ")
+            ));
+        }
+        xml.push_str(&format!("</{}>\n", element));
+    }
+    xml.push_str(&format!("</{}>\n", root));
+
+    xml
+}
+
+pub fn render_robots(disallow: &[String], sitemap_url: &str) -> String {
+    let mut robots = String::from("User-agent: *\n");
+    if disallow.is_empty() {
+        robots.push_str("Disallow:\n");
+    }
+    for path in disallow {
+        robots.push_str(&format!("Disallow: {}\n", path));
+    }
+    robots.push_str(&format!("\nSitemap: {}\n", sitemap_url));
+
+    robots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn urlset_escapes_locations() {
+        let entries = [
+            SitemapEntry {
+                loc: String::from("https://example.com/api/tags/c&c/articles?a='1'&b=<2>"),
+                lastmod: Some(Utc.with_ymd_and_hms(2023, 5, 1, 12, 30, 0).unwrap()),
+            },
+            SitemapEntry {
+                loc: String::from("https://example.com/api/u/tom"),
+                lastmod: None,
+            },
+        ];
+        let xml = render_urlset(&entries);
+
+        assert!(xml.contains("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">"));
+        assert!(xml.contains(
+            "<url><loc>https://example.com/api/tags/c&amp;c/articles?a=&#39;1&#39;&amp;b=&lt;2&gt;</loc><lastmod>2023-05-01T12:30:00Z</lastmod></url>"
+        ));
+        assert!(xml.contains("<url><loc>https://example.com/api/u/tom</loc></url>"));
+    }
+
+    #[test]
+    fn index_lists_sitemaps() {
+        let entries = [SitemapEntry {
+            loc: String::from("https://example.com/sitemaps/1.xml"),
+            lastmod: None,
+        }];
+        let xml = render_index(&entries);
+
+        assert!(xml.contains("<sitemapindex "));
+        assert!(xml.contains("<sitemap><loc>https://example.com/sitemaps/1.xml</loc></sitemap>"));
+        assert!(xml.ends_with("</sitemapindex>\n"));
+    }
+
+    #[test]
+    fn robots_allows_everything_without_disallowed_paths() {
+        let robots = render_robots(&[], "https://example.com/sitemap.xml");
+
+        assert_eq!(
+            robots,
+            "User-agent: *\nDisallow:\n\nSitemap: https://example.com/sitemap.xml\n"
+        );
+    }
+
+    #[test]
+    fn robots_lists_disallowed_paths() {
+        let disallow = [String::from("/api/login"), String::from("/api/users")];
+        let robots = render_robots(&disallow, "https://example.com/sitemap.xml");
+
+        assert!(robots.contains("Disallow: /api/login\nDisallow: /api/users\n"));
+        assert!(!robots.contains("Disallow:\n"));
+    }
+}
@@ -0,0 +1,135 @@
+// Spam scoring of comments: simple heuristics and a naive Bayes model trained by moderators
+use crate::models::spam::SpamCounts;
+use crate::utils::search;
+
+use std::collections::HashMap;
+
+// More links than this in a single comment is suspicious
+pub const MAX_LINKS: usize = 2;
+// The model stays out of the score until both classes have this many examples
+pub const MIN_TRAINING_DOCUMENTS: usize = 5;
+// Tokens looked at by the model, the ones pointing the most to either side
+const MAX_INTERESTING_TOKENS: usize = 20;
+
+pub fn tokens(content: &str) -> Vec<String> {
+    search::unique_terms(&search::tokenize(content))
+}
+
+pub fn count_links(content: &str) -> usize {
+    let lowercase = content.to_lowercase();
+    ["http://", "https://", "www."]
+        .iter()
+        .map(|pattern| lowercase.matches(pattern).count())
+        .sum::<usize>()
+        // `https://www.` would otherwise count twice
+        .saturating_sub(lowercase.matches("://www.").count())
+}
+
+// A blocked word is enough to hold a comment, every link above `MAX_LINKS` adds a quarter
+pub fn heuristic_score(content: &str, blocklist: &[String]) -> f64 {
+    let lowercase = content.to_lowercase();
+    if blocklist
+        .iter()
+        .any(|word| !word.is_empty() && lowercase.contains(&word.to_lowercase()))
+    {
+        return 1.0;
+    }
+
+    (count_links(content).saturating_sub(MAX_LINKS) as f64 * 0.25).min(1.0)
+}
+
+// Probability of being spam from the documents each token was seen in, with Laplace smoothing
+pub fn bayes_score(
+    documents: &SpamCounts,
+    counts: &HashMap<String, SpamCounts>,
+    tokens: &[String],
+) -> Option<f64> {
+    if documents.spam < MIN_TRAINING_DOCUMENTS || documents.ham < MIN_TRAINING_DOCUMENTS {
+        return None;
+    }
+
+    let spam = documents.spam as f64;
+    let ham = documents.ham as f64;
+    let mut evidence: Vec<f64> = tokens
+        .iter()
+        .filter_map(|token| counts.get(token))
+        .map(|count| {
+            let in_spam = (count.spam as f64 + 1.0) / (spam + 2.0);
+            let in_ham = (count.ham as f64 + 1.0) / (ham + 2.0);
+            (in_spam / in_ham).ln()
+        })
+        .collect();
+    evidence.sort_by(|first, second| second.abs().total_cmp(&first.abs()));
+    evidence.truncate(MAX_INTERESTING_TOKENS);
+
+    let log_odds = (spam / ham).ln() + evidence.iter().sum::<f64>();
+
+    Some(1.0 / (1.0 + (-log_odds).exp()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(spam: usize, ham: usize) -> SpamCounts {
+        SpamCounts { spam, ham }
+    }
+
+    #[test]
+    fn count_links_counts_each_link_once() {
+        assert_eq!(
+            count_links("See https://www.example.com, http://a.org and www.b.net"),
+            3
+        );
+        assert_eq!(count_links("no links here"), 0);
+    }
+
+    #[test]
+    fn heuristic_score_holds_blocked_words() {
+        let blocklist = vec![String::new(), String::from("Casino")];
+
+        assert_eq!(heuristic_score("Visit the online CASINO", &blocklist), 1.0);
+        assert_eq!(heuristic_score("A perfectly fine comment", &blocklist), 0.0);
+    }
+
+    #[test]
+    fn heuristic_score_grows_with_links() {
+        let links = |count: usize| "https://example.com ".repeat(count);
+
+        assert_eq!(heuristic_score(&links(MAX_LINKS), &[]), 0.0);
+        assert_eq!(heuristic_score(&links(MAX_LINKS + 2), &[]), 0.5);
+        assert_eq!(heuristic_score(&links(MAX_LINKS + 10), &[]), 1.0);
+    }
+
+    #[test]
+    fn tokens_are_unique_stems() {
+        assert_eq!(
+            tokens("Cheap pills, cheap PILLS!"),
+            vec![String::from("cheap"), String::from("pill")]
+        );
+    }
+
+    #[test]
+    fn bayes_score_needs_training() {
+        let documents = counts(MIN_TRAINING_DOCUMENTS - 1, MIN_TRAINING_DOCUMENTS);
+
+        assert_eq!(bayes_score(&documents, &HashMap::new(), &[]), None);
+    }
+
+    #[test]
+    fn bayes_score_follows_the_tokens() {
+        let documents = counts(10, 10);
+        let token_counts = HashMap::from([
+            (String::from("pill"), counts(9, 0)),
+            (String::from("articl"), counts(0, 9)),
+        ]);
+
+        let spam = bayes_score(&documents, &token_counts, &tokens("pills")).unwrap();
+        let ham = bayes_score(&documents, &token_counts, &tokens("article")).unwrap();
+        let unknown = bayes_score(&documents, &token_counts, &tokens("unknown")).unwrap();
+
+        assert!(spam > 0.9);
+        assert!(ham < 0.1);
+        assert!((unknown - 0.5).abs() < 1e-9);
+    }
+}
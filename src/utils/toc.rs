@@ -0,0 +1,267 @@
+// Word count, reading time and table of contents of an article body
+use crate::models::article::TocEntry;
+use crate::utils::{html, markdown};
+
+use pulldown_cmark::{Event, Tag};
+use std::collections::HashSet;
+
+const WORDS_PER_MINUTE: usize = 200;
+
+pub fn word_count(content: &str) -> usize {
+    html::to_plain_text(content).split_whitespace().count()
+}
+
+pub fn reading_time(word_count: usize) -> usize {
+    if word_count == 0 {
+        0
+    } else {
+        word_count.div_ceil(WORDS_PER_MINUTE)
+    }
+}
+
+// Gives every heading an `id` so the table of contents can link to it, existing ids are kept
+pub fn add_heading_anchors(document: &str) -> (String, Vec<TocEntry>) {
+    let lowercase = document.to_ascii_lowercase();
+    let mut result = String::with_capacity(document.len());
+    let mut toc = Vec::new();
+    let mut anchors = HashSet::new();
+    let mut position = 0;
+
+    while let Some((start, level)) = find_heading(&lowercase, position) {
+        let Some(open_end) = lowercase[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        let closing = format!("</h{}", level);
+        let Some(close_start) = lowercase[open_end..]
+            .find(&closing)
+            .map(|end| open_end + end)
+        else {
+            break;
+        };
+
+        let title = html::strip_tags(&document[open_end + 1..close_start])
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let attributes = &document[start + 3..open_end];
+        result.push_str(&document[position..open_end]);
+        let anchor = match existing_id(attributes) {
+            Some(id) => {
+                anchors.insert(id.clone());
+                id
+            }
+            None => {
+                let anchor = unique_anchor(&title, &mut anchors);
+                if attributes.ends_with('/') {
+                    result.pop();
+                }
+                result.push_str(&format!(" id=\"{}\"", anchor));
+                anchor
+            }
+        };
+        result.push_str(&document[open_end..close_start]);
+        position = close_start;
+
+        if !title.is_empty() {
+            toc.push(TocEntry {
+                level,
+                title,
+                anchor,
+            });
+        }
+    }
+    result.push_str(&document[position..]);
+
+    (result, toc)
+}
+
+pub fn table_of_contents(content: &str) -> Vec<TocEntry> {
+    if html::is_html(content) {
+        add_heading_anchors(content).1
+    } else {
+        markdown_toc(content)
+    }
+}
+
+// Markdown is stored as is, the anchors are added to the headings when it is rendered
+pub fn markdown_toc(markdown: &str) -> Vec<TocEntry> {
+    markdown_headings(markdown)
+        .into_iter()
+        .filter(|heading| !heading.title.is_empty())
+        .collect()
+}
+
+// Every heading of a Markdown body along with the anchor `markdown::render_article` gives it
+pub fn markdown_headings(markdown: &str) -> Vec<TocEntry> {
+    let mut headings = Vec::new();
+    let mut anchors = HashSet::new();
+    let mut current: Option<(usize, String)> = None;
+    for event in markdown::article_parser(markdown) {
+        match (event, &mut current) {
+            (Event::Start(Tag::Heading(level, ..)), _) => {
+                current = Some((level as usize, String::new()));
+            }
+            (Event::Text(text) | Event::Code(text), Some((_, title))) => title.push_str(&text),
+            (Event::SoftBreak | Event::HardBreak, Some((_, title))) => title.push(' '),
+            (Event::End(Tag::Heading(..)), Some((level, title))) => {
+                let title = title.split_whitespace().collect::<Vec<&str>>().join(" ");
+                let anchor = unique_anchor(&title, &mut anchors);
+                headings.push(TocEntry {
+                    level: *level,
+                    title,
+                    anchor,
+                });
+                current = None;
+            }
+            _ => {}
+        }
+    }
+
+    headings
+}
+
+fn find_heading(lowercase: &str, from: usize) -> Option<(usize, usize)> {
+    let mut position = from;
+    while let Some(start) = lowercase[position..]
+        .find("<h")
+        .map(|start| position + start)
+    {
+        let bytes = lowercase.as_bytes();
+        if let Some(level @ b'1'..=b'6') = bytes.get(start + 2) {
+            if matches!(
+                bytes.get(start + 3),
+                Some(b' ' | b'>' | b'\t' | b'\n' | b'/')
+            ) {
+                return Some((start, (level - b'0') as usize));
+            }
+        }
+        position = start + 2;
+    }
+
+    None
+}
+
+fn existing_id(attributes: &str) -> Option<String> {
+    let lowercase = attributes.to_ascii_lowercase();
+    let start = lowercase
+        .match_indices("id=")
+        .find(|(index, _)| *index == 0 || lowercase.as_bytes()[index - 1].is_ascii_whitespace())?
+        .0
+        + 3;
+    let value = &attributes[start..];
+    let id = match value.chars().next()? {
+        quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+        _ => value
+            .split(|character: char| character.is_whitespace())
+            .next()?,
+    };
+
+    Some(id.to_string()).filter(|id| !id.is_empty())
+}
+
+fn unique_anchor(title: &str, anchors: &mut HashSet<String>) -> String {
+    let mut base = slug::slugify(title);
+    if base.is_empty() {
+        base = String::from("section");
+    }
+
+    let mut anchor = base.clone();
+    let mut suffix = 2;
+    while anchors.contains(&anchor) {
+        anchor = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    anchors.insert(anchor.clone());
+
+    anchor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(toc: &[TocEntry]) -> Vec<(usize, &str, &str)> {
+        toc.iter()
+            .map(|entry| (entry.level, entry.title.as_str(), entry.anchor.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn heading_anchors_are_added_and_made_unique() {
+        let (html, toc) = add_heading_anchors(
+            "<h1>Hello World</h1><p>text</p><h2 class=\"x\">Hello  <em>World</em></h2><H3>?!</H3>",
+        );
+
+        assert_eq!(
+            html,
+            "<h1 id=\"hello-world\">Hello World</h1><p>text</p><h2 class=\"x\" id=\"hello-world-2\">Hello  <em>World</em></h2><H3 id=\"section\">?!</H3>"
+        );
+        assert_eq!(
+            entries(&toc),
+            [
+                (1, "Hello World", "hello-world"),
+                (2, "Hello World", "hello-world-2"),
+                (3, "?!", "section"),
+            ]
+        );
+    }
+
+    #[test]
+    fn existing_ids_are_kept_and_reserved() {
+        let (html, toc) = add_heading_anchors("<h2 id='intro'>Intro</h2><h2>Intro</h2>");
+
+        assert_eq!(
+            html,
+            "<h2 id='intro'>Intro</h2><h2 id=\"intro-2\">Intro</h2>"
+        );
+        assert_eq!(
+            entries(&toc),
+            [(2, "Intro", "intro"), (2, "Intro", "intro-2")]
+        );
+    }
+
+    #[test]
+    fn header_and_hr_tags_are_not_headings() {
+        let (html, toc) = add_heading_anchors("<header><hr><h7>no</h7></header>");
+
+        assert_eq!(html, "<header><hr><h7>no</h7></header>");
+        assert!(toc.is_empty());
+    }
+
+    #[test]
+    fn markdown_toc_skips_code_blocks() {
+        let toc = markdown_toc(
+            "# Getting *Started*\n\n```\n# not a heading\n```\n\n## Setup ##\n#hashtag\n## Setup\n",
+        );
+
+        assert_eq!(
+            entries(&toc),
+            [
+                (1, "Getting Started", "getting-started"),
+                (2, "Setup", "setup"),
+                (2, "Setup", "setup-2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_toc_anchors_are_rendered() {
+        let content = "# Getting Started\n\nText\n\n## Setup `cargo`\n\n## Setup `cargo`\n\n##\n";
+        let html = markdown::render_article(content);
+        let toc = markdown_toc(content);
+
+        assert_eq!(toc.len(), 3);
+        for entry in &toc {
+            assert!(html.contains(&format!(" id=\"{}\"", entry.anchor)));
+        }
+        assert!(html.contains("<h2 id=\"setup-cargo-2\">Setup <code>cargo</code></h2>"));
+    }
+
+    #[test]
+    fn reading_time_rounds_up() {
+        assert_eq!(reading_time(0), 0);
+        assert_eq!(reading_time(1), 1);
+        assert_eq!(reading_time(200), 1);
+        assert_eq!(reading_time(201), 2);
+    }
+}